@@ -0,0 +1,24 @@
+//! Generates the JSON snapshot `BiolinkModel::from_reader` reads from the `biolink-model.yaml` of a Biolink model
+//! release, restricted to the given terms and what they refer to when any are named:
+//!
+//! ```text
+//! cargo run --features biolink --example biolink_snapshot -- biolink-model.yaml [term ...] > snapshot.json
+//! ```
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader};
+use trapi_model_rs::biolink::BiolinkModel;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = env::args().skip(1);
+    let path = args.next().ok_or("usage: biolink_snapshot <biolink-model.yaml> [term ...]")?;
+    let model = BiolinkModel::from_yaml(BufReader::new(File::open(path)?))?;
+    let terms: Vec<String> = args.collect();
+    let model = match terms.is_empty() {
+        true => model,
+        false => model.subset(terms.iter().map(String::as_str))?,
+    };
+    model.write_json(io::stdout().lock())?;
+    println!();
+    Ok(())
+}
@@ -0,0 +1,755 @@
+use crate::{BiolinkEntity, BiolinkPredicate, CURIE};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::OnceLock;
+
+const BUNDLED_SUBSET: &str = include_str!("../data/biolink-model-subset.json");
+
+#[derive(Debug)]
+pub enum BiolinkModelError {
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    UnknownParent {
+        element: String,
+        parent: String,
+    },
+    /// a category, predicate, qualifier or qualifier value the model does not define
+    UnknownTerm(String),
+}
+
+impl fmt::Display for BiolinkModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BiolinkModelError::Json(e) => write!(f, "could not parse biolink model: {}", e),
+            BiolinkModelError::Yaml(e) => write!(f, "could not parse biolink model: {}", e),
+            BiolinkModelError::UnknownParent { element, parent } => write!(f, "{} refers to undefined element {}", element, parent),
+            BiolinkModelError::UnknownTerm(term) => write!(f, "{} is not defined in the biolink model", term),
+        }
+    }
+}
+
+impl std::error::Error for BiolinkModelError {}
+
+impl From<serde_json::Error> for BiolinkModelError {
+    fn from(e: serde_json::Error) -> Self {
+        BiolinkModelError::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for BiolinkModelError {
+    fn from(e: serde_yaml::Error) -> Self {
+        BiolinkModelError::Yaml(e)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClassDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_a: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mixins: Vec<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub mixin: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PredicateDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_a: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mixins: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inverse: Option<String>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub symmetric: bool,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub canonical: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualifierDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_a: Option<String>,
+
+    /// Name of the enum the qualifier's values are taken from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PermissibleValue {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_a: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: String,
+    classes: BTreeMap<String, ClassDefinition>,
+    predicates: BTreeMap<String, PredicateDefinition>,
+    #[serde(default)]
+    qualifiers: BTreeMap<String, QualifierDefinition>,
+    #[serde(default)]
+    enums: BTreeMap<String, BTreeMap<String, PermissibleValue>>,
+}
+
+/// The predicate to use for an edge written in its canonical direction; `inverted` means subject and object must be swapped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanonicalPredicate {
+    pub predicate: BiolinkPredicate,
+
+    pub inverted: bool,
+}
+
+/// Class, predicate and qualifier hierarchies of a Biolink model release, keyed by local name (`SmallMolecule`, `treats`).
+/// Lookups of terms the model does not define fail with `BiolinkModelError::UnknownTerm` rather than answering as if the
+/// term had no ancestors or descendants.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BiolinkModel {
+    version: String,
+    classes: BTreeMap<String, ClassDefinition>,
+    predicates: BTreeMap<String, PredicateDefinition>,
+    qualifiers: BTreeMap<String, QualifierDefinition>,
+    enums: BTreeMap<String, BTreeMap<String, PermissibleValue>>,
+    class_children: BTreeMap<String, BTreeSet<String>>,
+    predicate_children: BTreeMap<String, BTreeSet<String>>,
+}
+
+fn parents<'a>(is_a: &'a Option<String>, mixins: &'a [String]) -> impl Iterator<Item = &'a String> {
+    is_a.iter().chain(mixins.iter())
+}
+
+fn children<'a, I>(elements: I) -> Result<BTreeMap<String, BTreeSet<String>>, BiolinkModelError>
+where
+    I: Iterator<Item = (&'a String, Vec<&'a String>)> + Clone,
+{
+    let names: BTreeSet<&String> = elements.clone().map(|(name, _)| name).collect();
+    let mut children: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (name, parents) in elements {
+        for parent in parents {
+            if !names.contains(parent) {
+                return Err(BiolinkModelError::UnknownParent {
+                    element: name.clone(),
+                    parent: parent.clone(),
+                });
+            }
+            children.entry(parent.clone()).or_default().insert(name.clone());
+        }
+    }
+    Ok(children)
+}
+
+// breadth first, so closer ancestors come before more distant ones
+fn traverse<'a, F>(start: &'a str, next: F) -> Vec<String>
+where
+    F: Fn(&str) -> Vec<&'a String>,
+{
+    let mut seen = BTreeSet::new();
+    let mut found = vec![];
+    let mut queue: VecDeque<&str> = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for name in next(current) {
+            if name != start && seen.insert(name.as_str()) {
+                found.push(name.clone());
+                queue.push_back(name);
+            }
+        }
+    }
+    found
+}
+
+fn to_curies<T: std::str::FromStr>(names: Vec<String>) -> Vec<T> {
+    names.into_iter().filter_map(|name| format!("biolink:{}", name).parse().ok()).collect()
+}
+
+fn unknown(term: &str) -> BiolinkModelError {
+    BiolinkModelError::UnknownTerm(term.to_string())
+}
+
+// The parts of a LinkML schema, as in the `biolink-model.yaml` of a release, that the snapshot is built from.
+#[derive(Deserialize)]
+struct LinkmlSchema {
+    version: String,
+    #[serde(default)]
+    classes: BTreeMap<String, Option<LinkmlElement>>,
+    #[serde(default)]
+    slots: BTreeMap<String, Option<LinkmlElement>>,
+    #[serde(default)]
+    enums: BTreeMap<String, Option<LinkmlEnum>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LinkmlElement {
+    is_a: Option<String>,
+    mixins: Vec<String>,
+    mixin: bool,
+    inverse: Option<String>,
+    symmetric: bool,
+    range: Option<String>,
+    annotations: BTreeMap<String, serde_yaml::Value>,
+}
+
+impl LinkmlElement {
+    // annotations are written either as `tag: value` or as `tag: { tag: ..., value: ... }`
+    fn annotated(&self, tag: &str) -> bool {
+        let value = self.annotations.get(tag).map(|value| value.get("value").unwrap_or(value));
+        matches!(value, Some(serde_yaml::Value::Bool(true))) || value.and_then(|value| value.as_str()) == Some("true")
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LinkmlEnum {
+    permissible_values: serde_yaml::Mapping,
+}
+
+// `small molecule` -> `SmallMolecule`, `RNA product` -> `RNAProduct`
+fn class_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+// `treated by` -> `treated_by`
+fn slot_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+// `root` and every slot that has it as an ancestor, by their names in the schema
+fn slots_under<'a>(slots: &'a BTreeMap<String, LinkmlElement>, root: &str) -> BTreeSet<&'a str> {
+    slots
+        .keys()
+        .filter(|name| {
+            *name == root
+                || traverse(name, |name| slots.get(name).map(|s| parents(&s.is_a, &s.mixins).collect()).unwrap_or_default())
+                    .iter()
+                    .any(|a| a == root)
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+impl Snapshot {
+    fn from_linkml(schema: LinkmlSchema) -> Snapshot {
+        let slots: BTreeMap<String, LinkmlElement> = schema.slots.into_iter().map(|(name, slot)| (name, slot.unwrap_or_default())).collect();
+        let predicates = slots_under(&slots, "related to");
+        let qualifiers = slots_under(&slots, "qualifier");
+        // references to slots outside a hierarchy, such as a predicate's mixin that is not a predicate, are left out
+        let within = |names: &BTreeSet<&str>, name: &Option<String>| name.as_ref().filter(|name| names.contains(name.as_str())).map(|name| slot_name(name));
+        Snapshot {
+            version: schema.version,
+            classes: schema
+                .classes
+                .into_iter()
+                .map(|(name, class)| {
+                    let class = class.unwrap_or_default();
+                    let definition = ClassDefinition {
+                        is_a: class.is_a.as_deref().map(class_name),
+                        mixins: class.mixins.iter().map(|mixin| class_name(mixin)).collect(),
+                        mixin: class.mixin,
+                    };
+                    (class_name(&name), definition)
+                })
+                .collect(),
+            predicates: predicates
+                .iter()
+                .map(|name| {
+                    let slot = &slots[*name];
+                    let definition = PredicateDefinition {
+                        is_a: within(&predicates, &slot.is_a),
+                        mixins: slot
+                            .mixins
+                            .iter()
+                            .filter(|mixin| predicates.contains(mixin.as_str()))
+                            .map(|mixin| slot_name(mixin))
+                            .collect(),
+                        inverse: slot.inverse.as_deref().map(slot_name),
+                        symmetric: slot.symmetric,
+                        canonical: slot.annotated("canonical_predicate"),
+                    };
+                    (slot_name(name), definition)
+                })
+                .collect(),
+            qualifiers: qualifiers
+                .iter()
+                .map(|name| {
+                    let slot = &slots[*name];
+                    let definition = QualifierDefinition {
+                        is_a: within(&qualifiers, &slot.is_a),
+                        // only enum ranges have values to compare, not classes such as `OrganismTaxon`
+                        range: slot.range.clone().filter(|range| schema.enums.contains_key(range)),
+                    };
+                    (slot_name(name), definition)
+                })
+                .collect(),
+            enums: schema
+                .enums
+                .into_iter()
+                .map(|(name, values)| {
+                    let values = values.unwrap_or_default().permissible_values.into_iter().filter_map(|(value, definition)| {
+                        let is_a = definition.get("is_a").and_then(|is_a| is_a.as_str()).map(String::from);
+                        value.as_str().map(|value| (value.to_string(), PermissibleValue { is_a }))
+                    });
+                    (name, values.collect())
+                })
+                .collect(),
+        }
+    }
+}
+
+impl BiolinkModel {
+    /// A subset of Biolink 4.2.0 compiled into this crate, enough for tests and examples; `data/README.md` says how it is
+    /// generated. It is not the full release, so load that with `from_yaml` to check real data.
+    pub fn bundled_subset() -> &'static BiolinkModel {
+        static MODEL: OnceLock<BiolinkModel> = OnceLock::new();
+        MODEL.get_or_init(|| BiolinkModel::from_json_str(BUNDLED_SUBSET).expect("bundled biolink model subset is invalid"))
+    }
+
+    pub fn from_json_str(data: &str) -> Result<BiolinkModel, BiolinkModelError> {
+        BiolinkModel::from_snapshot(serde_json::from_str(data)?)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<BiolinkModel, BiolinkModelError> {
+        BiolinkModel::from_snapshot(serde_json::from_reader(reader)?)
+    }
+
+    /// Reads the `biolink-model.yaml` of a Biolink model release. Predicates are the slots under `related to` and
+    /// qualifiers the slots under `qualifier`; element names are converted to the form they take in CURIEs.
+    pub fn from_yaml<R: Read>(reader: R) -> Result<BiolinkModel, BiolinkModelError> {
+        BiolinkModel::from_snapshot(Snapshot::from_linkml(serde_yaml::from_reader(reader)?))
+    }
+
+    /// Writes the snapshot `from_json_str` and `from_reader` read back.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), BiolinkModelError> {
+        let snapshot = Snapshot {
+            version: self.version.clone(),
+            classes: self.classes.clone(),
+            predicates: self.predicates.clone(),
+            qualifiers: self.qualifiers.clone(),
+            enums: self.enums.clone(),
+        };
+        Ok(serde_json::to_writer_pretty(writer, &snapshot)?)
+    }
+
+    /// The part of this model that defines `terms`, local names of classes, predicates or qualifiers, along with
+    /// everything they refer to: their ancestors and mixins, the inverses of predicates and the enums of qualifiers.
+    pub fn subset<'a, I>(&self, terms: I) -> Result<BiolinkModel, BiolinkModelError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut snapshot = Snapshot {
+            version: self.version.clone(),
+            classes: BTreeMap::new(),
+            predicates: BTreeMap::new(),
+            qualifiers: BTreeMap::new(),
+            enums: BTreeMap::new(),
+        };
+        let mut queue: Vec<String> = terms.into_iter().map(String::from).collect();
+        while let Some(term) = queue.pop() {
+            if let Some(class) = self.classes.get(&term) {
+                if snapshot.classes.insert(term.clone(), class.clone()).is_none() {
+                    queue.extend(parents(&class.is_a, &class.mixins).cloned());
+                }
+            } else if let Some(predicate) = self.predicates.get(&term) {
+                if snapshot.predicates.insert(term.clone(), predicate.clone()).is_none() {
+                    queue.extend(parents(&predicate.is_a, &predicate.mixins).chain(predicate.inverse.iter()).cloned());
+                }
+            } else if let Some(qualifier) = self.qualifiers.get(&term) {
+                if snapshot.qualifiers.insert(term.clone(), qualifier.clone()).is_none() {
+                    queue.extend(qualifier.is_a.iter().cloned());
+                    if let Some(range) = &qualifier.range {
+                        snapshot.enums.insert(range.clone(), self.enums.get(range).cloned().ok_or_else(|| unknown(range))?);
+                    }
+                }
+            } else {
+                return Err(unknown(&term));
+            }
+        }
+        BiolinkModel::from_snapshot(snapshot)
+    }
+
+    fn from_snapshot(snapshot: Snapshot) -> Result<BiolinkModel, BiolinkModelError> {
+        let class_children = children(snapshot.classes.iter().map(|(name, c)| (name, parents(&c.is_a, &c.mixins).collect())))?;
+        let predicate_children = children(snapshot.predicates.iter().map(|(name, p)| (name, parents(&p.is_a, &p.mixins).collect())))?;
+        children(snapshot.qualifiers.iter().map(|(name, q)| (name, q.is_a.iter().collect())))?;
+        for values in snapshot.enums.values() {
+            children(values.iter().map(|(name, v)| (name, v.is_a.iter().collect())))?;
+        }
+        Ok(BiolinkModel {
+            version: snapshot.version,
+            classes: snapshot.classes,
+            predicates: snapshot.predicates,
+            qualifiers: snapshot.qualifiers,
+            enums: snapshot.enums,
+            class_children,
+            predicate_children,
+        })
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Whether a `Response::biolink_version` refers to the same major.minor release as this model.
+    pub fn is_compatible_with(&self, biolink_version: &str) -> bool {
+        let major_minor = |v: &str| v.trim_start_matches('v').split('.').take(2).map(String::from).collect::<Vec<_>>();
+        major_minor(&self.version) == major_minor(biolink_version)
+    }
+
+    pub fn class(&self, category: &BiolinkEntity) -> Option<&ClassDefinition> {
+        self.classes.get(category.local_id())
+    }
+
+    pub fn predicate(&self, predicate: &BiolinkPredicate) -> Option<&PredicateDefinition> {
+        self.predicates.get(predicate.local_id())
+    }
+
+    fn known_class(&self, category: &BiolinkEntity) -> Result<&ClassDefinition, BiolinkModelError> {
+        self.class(category).ok_or_else(|| unknown(category.as_str()))
+    }
+
+    fn known_predicate(&self, predicate: &BiolinkPredicate) -> Result<&PredicateDefinition, BiolinkModelError> {
+        self.predicate(predicate).ok_or_else(|| unknown(predicate.as_str()))
+    }
+
+    /// All superclasses and mixins of `category`, closest first.
+    pub fn category_ancestors(&self, category: &BiolinkEntity) -> Result<Vec<BiolinkEntity>, BiolinkModelError> {
+        self.known_class(category)?;
+        Ok(to_curies(traverse(category.local_id(), |name| {
+            self.classes.get(name).map(|c| parents(&c.is_a, &c.mixins).collect()).unwrap_or_default()
+        })))
+    }
+
+    /// All classes that have `category` as a superclass or mixin, closest first.
+    pub fn category_descendants(&self, category: &BiolinkEntity) -> Result<Vec<BiolinkEntity>, BiolinkModelError> {
+        self.known_class(category)?;
+        Ok(to_curies(traverse(category.local_id(), |name| {
+            self.class_children.get(name).map(|c| c.iter().collect()).unwrap_or_default()
+        })))
+    }
+
+    /// Reflexive: every known category is a subclass of itself.
+    pub fn is_subclass_of(&self, category: &BiolinkEntity, ancestor: &BiolinkEntity) -> Result<bool, BiolinkModelError> {
+        self.known_class(ancestor)?;
+        Ok(category == ancestor || self.category_ancestors(category)?.contains(ancestor))
+    }
+
+    pub fn mixins(&self, category: &BiolinkEntity) -> Result<Vec<BiolinkEntity>, BiolinkModelError> {
+        Ok(to_curies(self.known_class(category)?.mixins.clone()))
+    }
+
+    pub fn is_mixin(&self, category: &BiolinkEntity) -> Result<bool, BiolinkModelError> {
+        Ok(self.known_class(category)?.mixin)
+    }
+
+    pub fn predicate_ancestors(&self, predicate: &BiolinkPredicate) -> Result<Vec<BiolinkPredicate>, BiolinkModelError> {
+        self.known_predicate(predicate)?;
+        Ok(to_curies(traverse(predicate.local_id(), |name| {
+            self.predicates.get(name).map(|p| parents(&p.is_a, &p.mixins).collect()).unwrap_or_default()
+        })))
+    }
+
+    pub fn predicate_descendants(&self, predicate: &BiolinkPredicate) -> Result<Vec<BiolinkPredicate>, BiolinkModelError> {
+        self.known_predicate(predicate)?;
+        Ok(to_curies(traverse(predicate.local_id(), |name| {
+            self.predicate_children.get(name).map(|c| c.iter().collect()).unwrap_or_default()
+        })))
+    }
+
+    /// Reflexive: every known predicate is a subpredicate of itself.
+    pub fn is_subpredicate_of(&self, predicate: &BiolinkPredicate, ancestor: &BiolinkPredicate) -> Result<bool, BiolinkModelError> {
+        self.known_predicate(ancestor)?;
+        Ok(predicate == ancestor || self.predicate_ancestors(predicate)?.contains(ancestor))
+    }
+
+    /// The declared inverse, or the predicate itself when it is symmetric.
+    pub fn inverse(&self, predicate: &BiolinkPredicate) -> Result<Option<BiolinkPredicate>, BiolinkModelError> {
+        let definition = self.known_predicate(predicate)?;
+        Ok(match &definition.inverse {
+            Some(inverse) => format!("biolink:{}", inverse).parse().ok(),
+            None if definition.symmetric => Some(predicate.clone()),
+            None => None,
+        })
+    }
+
+    pub fn is_symmetric(&self, predicate: &BiolinkPredicate) -> Result<bool, BiolinkModelError> {
+        Ok(self.known_predicate(predicate)?.symmetric)
+    }
+
+    pub fn is_canonical(&self, predicate: &BiolinkPredicate) -> Result<bool, BiolinkModelError> {
+        Ok(self.known_predicate(predicate)?.canonical)
+    }
+
+    /// Symmetric predicates and predicates without a canonical inverse are already in their canonical direction.
+    pub fn canonical_predicate(&self, predicate: &BiolinkPredicate) -> Result<CanonicalPredicate, BiolinkModelError> {
+        let definition = self.known_predicate(predicate)?;
+        Ok(match self.inverse(predicate)? {
+            Some(inverse) if !definition.canonical && !definition.symmetric && self.is_canonical(&inverse)? => CanonicalPredicate {
+                predicate: inverse,
+                inverted: true,
+            },
+            _ => CanonicalPredicate {
+                predicate: predicate.clone(),
+                inverted: false,
+            },
+        })
+    }
+
+    pub fn qualifier(&self, qualifier_type_id: &CURIE) -> Option<&QualifierDefinition> {
+        match qualifier_type_id.prefix() {
+            "biolink" => self.qualifiers.get(qualifier_type_id.local_id()),
+            _ => None,
+        }
+    }
+
+    // None for qualifiers whose values are not taken from an enum, such as `species_context_qualifier`
+    fn qualifier_values(&self, qualifier_type_id: &CURIE) -> Result<Option<&BTreeMap<String, PermissibleValue>>, BiolinkModelError> {
+        let qualifier = self.qualifier(qualifier_type_id).ok_or_else(|| unknown(qualifier_type_id.as_str()))?;
+        match &qualifier.range {
+            Some(range) => self.enums.get(range).map(Some).ok_or_else(|| unknown(range)),
+            None => Ok(None),
+        }
+    }
+
+    /// The broader values of `value` within the enum `qualifier_type_id` ranges over, closest first,
+    /// e.g. `activity_or_abundance` for an `object_aspect_qualifier` of `activity`. Qualifiers whose values are not
+    /// taken from an enum have no hierarchy.
+    pub fn qualifier_value_ancestors(&self, qualifier_type_id: &CURIE, value: &str) -> Result<Vec<String>, BiolinkModelError> {
+        match self.qualifier_values(qualifier_type_id)? {
+            Some(values) if values.contains_key(value) => Ok(traverse(value, |name| values.get(name).map(|v| v.is_a.iter().collect()).unwrap_or_default())),
+            Some(_) => Err(unknown(value)),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Reflexive: every permissible value of the qualifier is subsumed by itself.
+    pub fn is_qualifier_value_subsumed_by(&self, qualifier_type_id: &CURIE, value: &str, ancestor: &str) -> Result<bool, BiolinkModelError> {
+        if let Some(values) = self.qualifier_values(qualifier_type_id)? {
+            if !values.contains_key(ancestor) {
+                return Err(unknown(ancestor));
+            }
+        }
+        Ok(value == ancestor || self.qualifier_value_ancestors(qualifier_type_id, value)?.iter().any(|a| a == ancestor))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::biolink::{BiolinkModel, BiolinkModelError, CanonicalPredicate};
+    use crate::{BiolinkEntity, BiolinkPredicate, CURIE};
+
+    fn entity(name: &str) -> BiolinkEntity {
+        name.parse().unwrap()
+    }
+
+    fn predicate(name: &str) -> BiolinkPredicate {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn class_hierarchy() {
+        let model = BiolinkModel::bundled_subset();
+        assert!(model.is_compatible_with("4.2.1"));
+        assert!(!model.is_compatible_with("3.1.2"));
+
+        assert!(model.is_subclass_of(&entity("biolink:SmallMolecule"), &entity("biolink:ChemicalEntity")).unwrap());
+        assert!(model.is_subclass_of(&entity("biolink:Protein"), &entity("biolink:GeneOrGeneProduct")).unwrap());
+        assert!(!model.is_subclass_of(&entity("biolink:Gene"), &entity("biolink:ChemicalEntity")).unwrap());
+
+        let ancestors = model.category_ancestors(&entity("biolink:SmallMolecule")).unwrap();
+        assert_eq!(&ancestors[..2], &[entity("biolink:MolecularEntity"), entity("biolink:ChemicalEntity")]);
+        assert!(ancestors.contains(&entity("biolink:NamedThing")));
+        assert!(ancestors.contains(&entity("biolink:PhysicalEssence")));
+
+        let descendants = model.category_descendants(&entity("biolink:DiseaseOrPhenotypicFeature")).unwrap();
+        assert_eq!(
+            descendants,
+            vec![
+                entity("biolink:Disease"),
+                entity("biolink:PhenotypicFeature"),
+                entity("biolink:BehavioralFeature"),
+                entity("biolink:ClinicalFinding")
+            ]
+        );
+        assert!(model
+            .category_descendants(&entity("biolink:GeneOrGeneProduct"))
+            .unwrap()
+            .contains(&entity("biolink:ProteinIsoform")));
+        assert!(model.is_mixin(&entity("biolink:GeneOrGeneProduct")).unwrap());
+        assert!(model.mixins(&entity("biolink:Protein")).unwrap().contains(&entity("biolink:GeneProductMixin")));
+    }
+
+    #[test]
+    fn unknown_terms() {
+        let model = BiolinkModel::bundled_subset();
+        let donkey = entity("biolink:Donkey");
+        assert!(matches!(model.is_subclass_of(&donkey, &donkey), Err(BiolinkModelError::UnknownTerm(term)) if term == "biolink:Donkey"));
+        assert!(model.is_subclass_of(&entity("biolink:Gene"), &donkey).is_err());
+        assert!(model.category_ancestors(&donkey).is_err());
+        assert!(model.category_descendants(&donkey).is_err());
+        assert!(model.is_mixin(&donkey).is_err());
+
+        let not_a_predicate = predicate("biolink:not_a_predicate");
+        assert!(model.canonical_predicate(&not_a_predicate).is_err());
+        assert!(model.inverse(&not_a_predicate).is_err());
+        assert!(model.is_subpredicate_of(&predicate("biolink:treats"), &not_a_predicate).is_err());
+
+        let direction: CURIE = "biolink:object_direction_qualifier".parse().unwrap();
+        assert!(matches!(model.is_qualifier_value_subsumed_by(&direction, "activity", "activity"), Err(BiolinkModelError::UnknownTerm(term)) if term == "activity"));
+        let unknown: CURIE = "biolink:not_a_qualifier".parse().unwrap();
+        assert!(model.qualifier_value_ancestors(&unknown, "activity").is_err());
+    }
+
+    #[test]
+    fn predicate_hierarchy() {
+        let model = BiolinkModel::bundled_subset();
+        assert!(model.is_subpredicate_of(&predicate("biolink:treats"), &predicate("biolink:related_to")).unwrap());
+        assert!(model
+            .predicate_descendants(&predicate("biolink:affects"))
+            .unwrap()
+            .contains(&predicate("biolink:regulates")));
+
+        assert_eq!(model.inverse(&predicate("biolink:treats")).unwrap(), Some(predicate("biolink:treated_by")));
+        assert_eq!(model.inverse(&predicate("biolink:interacts_with")).unwrap(), Some(predicate("biolink:interacts_with")));
+        assert!(model.is_symmetric(&predicate("biolink:correlated_with")).unwrap());
+        assert!(!model.is_symmetric(&predicate("biolink:treats")).unwrap());
+
+        assert_eq!(
+            model.canonical_predicate(&predicate("biolink:treated_by")).unwrap(),
+            CanonicalPredicate {
+                predicate: predicate("biolink:treats"),
+                inverted: true
+            }
+        );
+        assert_eq!(
+            model.canonical_predicate(&predicate("biolink:treats")).unwrap(),
+            CanonicalPredicate {
+                predicate: predicate("biolink:treats"),
+                inverted: false
+            }
+        );
+    }
+
+    #[test]
+    fn qualifier_values() {
+        let model = BiolinkModel::bundled_subset();
+        let aspect: CURIE = "biolink:object_aspect_qualifier".parse().unwrap();
+        assert_eq!(model.qualifier(&aspect).and_then(|q| q.is_a.as_deref()), Some("aspect_qualifier"));
+        assert!(model.is_qualifier_value_subsumed_by(&aspect, "activity", "activity_or_abundance").unwrap());
+        assert!(model.is_qualifier_value_subsumed_by(&aspect, "activity", "activity").unwrap());
+        assert!(!model.is_qualifier_value_subsumed_by(&aspect, "activity_or_abundance", "activity").unwrap());
+        assert_eq!(model.qualifier_value_ancestors(&aspect, "secretion").unwrap(), vec!["transport", "localization"]);
+
+        let direction: CURIE = "biolink:object_direction_qualifier".parse().unwrap();
+        assert!(model.is_qualifier_value_subsumed_by(&direction, "upregulated", "increased").unwrap());
+
+        let species: CURIE = "biolink:species_context_qualifier".parse().unwrap();
+        assert!(model.qualifier_value_ancestors(&species, "NCBITaxon:9606").unwrap().is_empty());
+        assert!(model.is_qualifier_value_subsumed_by(&species, "NCBITaxon:9606", "NCBITaxon:9606").unwrap());
+    }
+
+    const LINKML: &str = r#"
+id: https://w3id.org/biolink/biolink-model
+version: 4.2.0
+slots:
+  id:
+    identifier: true
+  related to:
+    symmetric: true
+    annotations:
+      canonical_predicate: true
+  related to at instance level:
+    is_a: related to
+  treats or applied or studied to treat:
+    is_a: related to at instance level
+  treats:
+    is_a: treats or applied or studied to treat
+    inverse: treated by
+    annotations:
+      canonical_predicate:
+        tag: canonical_predicate
+        value: true
+  treated by:
+    is_a: related to at instance level
+    inverse: treats
+  qualifier:
+  aspect qualifier:
+    is_a: qualifier
+  object aspect qualifier:
+    is_a: aspect qualifier
+    range: GeneOrGeneProductOrChemicalEntityAspectEnum
+  species context qualifier:
+    is_a: qualifier
+    range: organism taxon
+classes:
+  named thing:
+  organism taxon:
+    is_a: named thing
+  thing with taxon:
+    mixin: true
+  RNA product:
+    is_a: named thing
+    mixins:
+      - thing with taxon
+enums:
+  GeneOrGeneProductOrChemicalEntityAspectEnum:
+    permissible_values:
+      activity_or_abundance:
+      activity:
+        is_a: activity_or_abundance
+"#;
+
+    #[test]
+    fn linkml_schema() {
+        let model = BiolinkModel::from_yaml(LINKML.as_bytes()).unwrap();
+        assert_eq!(model.version(), "4.2.0");
+        assert_eq!(model.mixins(&entity("biolink:RNAProduct")).unwrap(), vec![entity("biolink:ThingWithTaxon")]);
+        assert!(model.is_mixin(&entity("biolink:ThingWithTaxon")).unwrap());
+
+        assert!(model.predicate(&predicate("biolink:id")).is_none());
+        assert!(model.is_subpredicate_of(&predicate("biolink:treats"), &predicate("biolink:related_to")).unwrap());
+        assert!(model.is_canonical(&predicate("biolink:treats")).unwrap());
+        assert!(model.is_canonical(&predicate("biolink:related_to")).unwrap());
+        assert_eq!(model.canonical_predicate(&predicate("biolink:treated_by")).unwrap().predicate, predicate("biolink:treats"));
+
+        let aspect: CURIE = "biolink:object_aspect_qualifier".parse().unwrap();
+        assert!(model.is_qualifier_value_subsumed_by(&aspect, "activity", "activity_or_abundance").unwrap());
+        let species: CURIE = "biolink:species_context_qualifier".parse().unwrap();
+        assert_eq!(model.qualifier(&species).unwrap().range, None);
+
+        let subset = model.subset(["RNAProduct", "treated_by", "object_aspect_qualifier"]).unwrap();
+        assert!(subset.class(&entity("biolink:OrganismTaxon")).is_none());
+        assert!(subset.is_subclass_of(&entity("biolink:RNAProduct"), &entity("biolink:ThingWithTaxon")).unwrap());
+        assert!(subset.is_canonical(&predicate("biolink:treats")).unwrap());
+        assert!(subset.qualifier_value_ancestors(&aspect, "activity").is_ok());
+        assert!(subset.qualifier(&species).is_none());
+        assert!(matches!(model.subset(["Donkey"]), Err(BiolinkModelError::UnknownTerm(term)) if term == "Donkey"));
+
+        let mut json = vec![];
+        subset.write_json(&mut json).unwrap();
+        assert_eq!(BiolinkModel::from_reader(&json[..]).unwrap(), subset);
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        let data = r#"{ "version": "4.2.0", "classes": { "Gene": { "is_a": "BiologicalEntity" } }, "predicates": {} }"#;
+        assert!(BiolinkModel::from_json_str(data).is_err());
+    }
+}
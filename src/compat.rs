@@ -0,0 +1,252 @@
+use crate::{Response, CURIE};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod v1_3;
+pub mod v1_4;
+
+#[derive(Debug)]
+pub enum CompatError {
+    Json(serde_json::Error),
+    UnsupportedVersion(String),
+}
+
+impl fmt::Display for CompatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatError::Json(e) => write!(f, "could not convert response: {}", e),
+            CompatError::UnsupportedVersion(version) => write!(f, "unsupported TRAPI version: {}", version),
+        }
+    }
+}
+
+impl std::error::Error for CompatError {}
+
+impl From<serde_json::Error> for CompatError {
+    fn from(e: serde_json::Error) -> Self {
+        CompatError::Json(e)
+    }
+}
+
+/// The TRAPI minor versions this crate can read and write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    V1_3,
+    V1_4,
+    V1_5,
+}
+
+impl SchemaVersion {
+    pub const CURRENT: SchemaVersion = SchemaVersion::V1_5;
+
+    /// The `schema_version` written into converted responses.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaVersion::V1_3 => "1.3.0",
+            SchemaVersion::V1_4 => "1.4.0",
+            SchemaVersion::V1_5 => "1.5.0",
+        }
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SchemaVersion {
+    type Err = CompatError;
+
+    /// Only the major and minor parts are looked at, so `1.4`, `1.4.2` and `1.4.0-beta` are all 1.4.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().trim_start_matches('v').split(['.', '-']);
+        match (parts.next(), parts.next()) {
+            (Some("1"), Some("3")) => Ok(SchemaVersion::V1_3),
+            (Some("1"), Some("4")) => Ok(SchemaVersion::V1_4),
+            (Some("1"), Some("5")) => Ok(SchemaVersion::V1_5),
+            _ => Err(CompatError::UnsupportedVersion(s.to_string())),
+        }
+    }
+}
+
+/// Reads a `Response` of whichever version its `schema_version` names and upgrades it to the current model; a
+/// response without `schema_version` is taken to be current. 1.3 results have no analyses, so each becomes a single
+/// analysis attributed to `resource_id`, the resource that answered.
+pub fn upgrade(value: Value, resource_id: &CURIE) -> Result<Response, CompatError> {
+    let version = match value.get("schema_version").and_then(Value::as_str) {
+        Some(version) => version.parse()?,
+        None => SchemaVersion::CURRENT,
+    };
+    let mut response = match version {
+        SchemaVersion::V1_3 => serde_json::from_value::<v1_3::Response>(value)?.upgrade(resource_id),
+        SchemaVersion::V1_4 => serde_json::from_value::<v1_4::Response>(value)?.into(),
+        SchemaVersion::V1_5 => return Ok(serde_json::from_value(value)?),
+    };
+    response.schema_version = Some(SchemaVersion::CURRENT.as_str().to_string());
+    Ok(response)
+}
+
+/// Converts `response` into the shape `version` clients expect, with `schema_version` set to match. What the older
+/// version cannot express is dropped: downgrading to 1.3 drops auxiliary graphs and flattens analyses into one set of
+/// edge bindings per result.
+pub fn downgrade(response: Response, version: SchemaVersion) -> Result<Value, CompatError> {
+    let mut value = match version {
+        SchemaVersion::V1_3 => serde_json::to_value(v1_3::Response::from(response))?,
+        SchemaVersion::V1_4 => serde_json::to_value(v1_4::Response::from(response))?,
+        SchemaVersion::V1_5 => serde_json::to_value(response)?,
+    };
+    value["schema_version"] = Value::String(version.as_str().to_string());
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compat::{downgrade, upgrade, CompatError, SchemaVersion};
+    use crate::{ResourceRoleEnum, SetInterpretationEnum, CURIE};
+    use serde_json::Value;
+
+    const V1_4: &str = r#"{
+        "schema_version": "1.4.2",
+        "message": {
+            "query_graph": {
+                "nodes": { "n0": { "categories": ["biolink:Gene"], "is_set": true }, "n1": { "ids": ["MONDO:0005148"] } },
+                "edges": { "e0": { "subject": "n0", "object": "n1" } }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "NCBIGene:5468": { "categories": ["biolink:Gene"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "e01": {
+                        "subject": "NCBIGene:5468", "predicate": "biolink:gene_associated_with_condition", "object": "MONDO:0005148",
+                        "attributes": [
+                            { "attribute_type_id": "biolink:primary_knowledge_source", "value": "infores:gwas-catalog" },
+                            { "attribute_type_id": "biolink:aggregator_knowledge_source", "value": ["infores:kp0"] },
+                            { "attribute_type_id": "biolink:p_value", "value": 1e-8 }
+                        ]
+                    }
+                }
+            },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "NCBIGene:5468" }], "n1": [{ "id": "MONDO:0005148" }] },
+                "analyses": [{ "resource_id": "infores:kp0", "score": 0.5, "edge_bindings": { "e0": [{ "id": "e01" }] } }]
+            }]
+        }
+    }"#;
+
+    const V1_3: &str = r#"{
+        "schema_version": "1.3.0",
+        "message": {
+            "knowledge_graph": {
+                "nodes": {
+                    "NCBIGene:5468": { "categories": ["biolink:Gene"] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"] }
+                },
+                "edges": {
+                    "e01": {
+                        "subject": "NCBIGene:5468", "predicate": "biolink:gene_associated_with_condition", "object": "MONDO:0005148",
+                        "attributes": [{ "attribute_type_id": "biolink:primary_knowledge_source", "value": "infores:gwas-catalog" }]
+                    }
+                }
+            },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "NCBIGene:5468" }], "n1": [{ "id": "MONDO:0005148" }] },
+                "edge_bindings": { "e0": [{ "id": "e01" }] },
+                "score": 0.25
+            }]
+        }
+    }"#;
+
+    fn kp0() -> CURIE {
+        "infores:kp0".parse().unwrap()
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!("1.4.0-beta".parse::<SchemaVersion>().unwrap(), SchemaVersion::V1_4);
+        assert_eq!("1.5".parse::<SchemaVersion>().unwrap(), SchemaVersion::V1_5);
+        assert!(matches!("1.2.0".parse::<SchemaVersion>(), Err(CompatError::UnsupportedVersion(_))));
+        assert!(upgrade(serde_json::json!({ "schema_version": "2.0.0", "message": {} }), &kp0()).is_err());
+    }
+
+    #[test]
+    fn upgrade_v1_4() {
+        let response = upgrade(serde_json::from_str(V1_4).unwrap(), &kp0()).unwrap();
+        assert_eq!(response.schema_version.as_deref(), Some("1.5.0"));
+
+        let message = response.message;
+        let qnode = &message.query_graph.as_ref().unwrap().nodes["n0"];
+        assert_eq!(qnode.set_interpretation, Some(SetInterpretationEnum::ALL));
+
+        let edge = &message.knowledge_graph.as_ref().unwrap().edges["e01"];
+        assert_eq!(edge.sources.len(), 2);
+        assert!(edge
+            .sources
+            .iter()
+            .any(|s| s.resource_role == ResourceRoleEnum::PrimaryKnowledgeSource && s.resource_id == "infores:gwas-catalog".parse::<CURIE>().unwrap()));
+        let aggregator = edge.sources.iter().find(|s| s.resource_role == ResourceRoleEnum::AggregatorKnowledgeSource).unwrap();
+        assert_eq!(aggregator.upstream_resource_ids, Some(vec!["infores:gwas-catalog".parse().unwrap()]));
+        assert_eq!(edge.attributes.as_ref().unwrap().len(), 1);
+        assert_eq!(message.results.unwrap()[0].analyses[0].score, Some(0.5));
+    }
+
+    #[test]
+    fn upgrade_v1_3() {
+        let response = upgrade(serde_json::from_str(V1_3).unwrap(), &kp0()).unwrap();
+        let results = response.message.results.unwrap();
+        assert_eq!(results[0].analyses.len(), 1);
+        assert_eq!(results[0].analyses[0].resource_id, kp0());
+        assert_eq!(results[0].analyses[0].score, Some(0.25));
+        assert_eq!(results[0].analyses[0].edge_bindings["e0"][0].id, "e01");
+        assert_eq!(response.message.knowledge_graph.unwrap().edges["e01"].sources.len(), 1);
+    }
+
+    #[test]
+    fn round_trip() {
+        let response = upgrade(serde_json::from_str(V1_4).unwrap(), &kp0()).unwrap();
+
+        let legacy = downgrade(response.clone(), SchemaVersion::V1_4).unwrap();
+        assert_eq!(legacy["schema_version"], "1.4.0");
+        assert_eq!(legacy["message"]["query_graph"]["nodes"]["n0"]["is_set"], true);
+        let attributes = legacy["message"]["knowledge_graph"]["edges"]["e01"]["attributes"].as_array().unwrap();
+        assert!(attributes
+            .iter()
+            .any(|a| a["attribute_type_id"] == "biolink:primary_knowledge_source" && a["value"] == "infores:gwas-catalog"));
+        assert_eq!(upgrade(legacy, &kp0()).unwrap(), response);
+
+        let legacy = downgrade(response.clone(), SchemaVersion::V1_3).unwrap();
+        let result = &legacy["message"]["results"][0];
+        assert_eq!(result["score"], 0.5);
+        assert_eq!(result["edge_bindings"]["e0"][0]["id"], "e01");
+        assert!(result.get("analyses").is_none());
+        assert!(legacy["message"]["knowledge_graph"]["edges"]["e01"].get("sources").is_none());
+        assert_eq!(upgrade(legacy, &kp0()).unwrap(), response);
+
+        assert_eq!(downgrade(response.clone(), SchemaVersion::V1_5).unwrap(), serde_json::to_value(&response).unwrap());
+        assert_eq!(downgrade(response, SchemaVersion::V1_5).unwrap()["schema_version"], Value::from("1.5.0"));
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn extensions_survive_conversion() {
+        let mut value: Value = serde_json::from_str(V1_4).unwrap();
+        value["x-trace"] = Value::from("abc");
+        value["message"]["knowledge_graph"]["edges"]["e01"]["x-confidence"] = Value::from(0.75);
+        value["message"]["results"][0]["node_bindings"]["n0"][0]["x-match"] = Value::from("exact");
+        let response = upgrade(value, &kp0()).unwrap();
+        assert_eq!(response.extensions["x-trace"], "abc");
+        assert_eq!(response.message.knowledge_graph.as_ref().unwrap().edges["e01"].extensions["x-confidence"], 0.75);
+        assert_eq!(response.message.results.as_ref().unwrap()[0].node_bindings["n0"][0].extensions["x-match"], "exact");
+
+        let legacy = downgrade(response.clone(), SchemaVersion::V1_4).unwrap();
+        assert_eq!(legacy["message"]["knowledge_graph"]["edges"]["e01"]["x-confidence"], 0.75);
+        assert_eq!(upgrade(legacy, &kp0()).unwrap(), response);
+
+        let legacy = downgrade(response, SchemaVersion::V1_3).unwrap();
+        assert_eq!(legacy["x-trace"], "abc");
+        assert_eq!(legacy["message"]["results"][0]["node_bindings"]["n0"][0]["x-match"], "exact");
+    }
+}
@@ -0,0 +1,165 @@
+//! TRAPI 1.3 shapes. On top of the 1.4 differences, results bind edges and carry a score directly rather than
+//! through analyses, edges have no `sources`, and there are no auxiliary graphs.
+
+use crate::compat::v1_4::{self, convert_bindings, EdgeBinding, KnowledgeGraph, NodeBinding, QueryGraph};
+use crate::{LogEntry, Workflow, CURIE};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+use std::collections::BTreeMap;
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Result {
+    pub node_bindings: BTreeMap<String, Vec<NodeBinding>>,
+
+    pub edge_bindings: BTreeMap<String, Vec<EdgeBinding>>,
+
+    pub score: Option<f64>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Message {
+    pub query_graph: Option<QueryGraph>,
+
+    pub knowledge_graph: Option<KnowledgeGraph>,
+
+    pub results: Option<Vec<Result>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub workflow: Option<Vec<Workflow>>,
+
+    pub message: Message,
+
+    pub status: Option<String>,
+
+    pub description: Option<String>,
+
+    pub logs: Option<Vec<LogEntry>>,
+
+    pub schema_version: Option<String>,
+
+    pub biolink_version: Option<String>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+impl Result {
+    /// Turns the result's edge bindings and score into a single analysis by `resource_id`.
+    pub fn upgrade(self, resource_id: &CURIE) -> crate::Result {
+        let mut analysis = crate::Analysis::new(resource_id.clone(), convert_bindings(self.edge_bindings));
+        analysis.score = self.score;
+        crate::Result {
+            #[cfg(feature = "extensions")]
+            extensions: self.extensions,
+            ..crate::Result::new(convert_bindings(self.node_bindings), vec![analysis])
+        }
+    }
+}
+
+impl From<crate::Result> for Result {
+    /// Binds every edge any analysis binds, and scores the result with its best analysis score.
+    fn from(result: crate::Result) -> Self {
+        let score = result.analyses.iter().filter_map(|a| a.score).map(OrderedFloat).max().map(|s| s.0);
+        let mut edge_bindings: BTreeMap<String, Vec<EdgeBinding>> = BTreeMap::new();
+        for (qedge_id, bindings) in result.analyses.into_iter().flat_map(|a| a.edge_bindings) {
+            let existing = edge_bindings.entry(qedge_id).or_default();
+            for binding in bindings {
+                match existing.iter_mut().find(|b| b.id == binding.id) {
+                    #[cfg(feature = "extensions")]
+                    Some(found) => crate::merge_extensions(&mut found.extensions, binding.extensions),
+                    #[cfg(not(feature = "extensions"))]
+                    Some(_) => {}
+                    None => existing.push(binding.into()),
+                }
+            }
+        }
+        Result {
+            node_bindings: convert_bindings(result.node_bindings),
+            edge_bindings,
+            score,
+            #[cfg(feature = "extensions")]
+            extensions: result.extensions,
+        }
+    }
+}
+
+impl Message {
+    pub fn upgrade(self, resource_id: &CURIE) -> crate::Message {
+        let message = v1_4::Message {
+            query_graph: self.query_graph,
+            knowledge_graph: self.knowledge_graph,
+            auxiliary_graphs: None,
+            results: None,
+            #[cfg(feature = "extensions")]
+            extensions: self.extensions,
+        };
+        crate::Message {
+            results: self.results.map(|results| results.into_iter().map(|r| r.upgrade(resource_id)).collect()),
+            ..message.into()
+        }
+    }
+}
+
+impl From<crate::Message> for Message {
+    fn from(message: crate::Message) -> Self {
+        let knowledge_graph = message.knowledge_graph.map(|kg| {
+            let mut kg = KnowledgeGraph::from(kg);
+            kg.edges.values_mut().for_each(|edge| edge.sources = None);
+            kg
+        });
+        Message {
+            query_graph: message.query_graph.map(Into::into),
+            knowledge_graph,
+            results: message.results.map(|results| results.into_iter().map(Into::into).collect()),
+            #[cfg(feature = "extensions")]
+            extensions: message.extensions,
+        }
+    }
+}
+
+impl Response {
+    pub fn upgrade(self, resource_id: &CURIE) -> crate::Response {
+        crate::Response {
+            workflow: self.workflow,
+            message: self.message.upgrade(resource_id),
+            status: self.status,
+            description: self.description,
+            logs: self.logs,
+            schema_version: self.schema_version,
+            biolink_version: self.biolink_version,
+            #[cfg(feature = "extensions")]
+            extensions: self.extensions,
+        }
+    }
+}
+
+impl From<crate::Response> for Response {
+    fn from(response: crate::Response) -> Self {
+        Response {
+            workflow: response.workflow,
+            message: response.message.into(),
+            status: response.status,
+            description: response.description,
+            logs: response.logs,
+            schema_version: response.schema_version,
+            biolink_version: response.biolink_version,
+            #[cfg(feature = "extensions")]
+            extensions: response.extensions,
+        }
+    }
+}
@@ -0,0 +1,595 @@
+//! TRAPI 1.4 shapes, where they differ from the current model: query nodes use `is_set` rather than
+//! `set_interpretation`, edges may carry their knowledge sources as `biolink:*_knowledge_source` attributes instead of
+//! `sources`, and attribute lists may be left out.
+
+use crate::{
+    Attribute, AttributeConstraint, BiolinkEntity, BiolinkPredicate, LogEntry, QEdge, Qualifier, ResourceRoleEnum, RetrievalSource, SetInterpretationEnum, Workflow, CURIE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_with::skip_serializing_none;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const PRIMARY_KNOWLEDGE_SOURCE: &str = "biolink:primary_knowledge_source";
+const AGGREGATOR_KNOWLEDGE_SOURCE: &str = "biolink:aggregator_knowledge_source";
+const SUPPORTING_DATA_SOURCE: &str = "biolink:supporting_data_source";
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct QNode {
+    pub ids: Option<Vec<CURIE>>,
+
+    pub categories: Option<Vec<BiolinkEntity>>,
+
+    pub is_set: Option<bool>,
+
+    pub constraints: Option<Vec<AttributeConstraint>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct QueryGraph {
+    pub edges: BTreeMap<String, QEdge>,
+    pub nodes: BTreeMap<String, QNode>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Node {
+    pub name: Option<String>,
+
+    pub categories: BTreeSet<BiolinkEntity>,
+
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+
+    pub is_set: Option<bool>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub subject: CURIE,
+
+    pub predicate: BiolinkPredicate,
+
+    pub object: CURIE,
+
+    pub sources: Option<Vec<RetrievalSource>>,
+
+    pub attributes: Option<Vec<Attribute>>,
+
+    pub qualifiers: Option<Vec<Qualifier>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct KnowledgeGraph {
+    pub nodes: HashMap<String, Node>,
+
+    pub edges: HashMap<String, Edge>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeBinding {
+    pub id: CURIE,
+
+    pub query_id: Option<CURIE>,
+
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EdgeBinding {
+    pub id: String,
+
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Analysis {
+    pub resource_id: CURIE,
+
+    pub score: Option<f64>,
+
+    pub scoring_method: Option<String>,
+
+    pub support_graphs: Option<Vec<String>>,
+
+    pub edge_bindings: BTreeMap<String, Vec<EdgeBinding>>,
+
+    pub attributes: Option<Vec<Attribute>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Result {
+    pub node_bindings: BTreeMap<String, Vec<NodeBinding>>,
+
+    pub analyses: Vec<Analysis>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct AuxiliaryGraph {
+    pub edges: Vec<String>,
+
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Message {
+    pub query_graph: Option<QueryGraph>,
+
+    pub knowledge_graph: Option<KnowledgeGraph>,
+
+    pub auxiliary_graphs: Option<BTreeMap<String, AuxiliaryGraph>>,
+
+    pub results: Option<Vec<Result>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub workflow: Option<Vec<Workflow>>,
+
+    pub message: Message,
+
+    pub status: Option<String>,
+
+    pub description: Option<String>,
+
+    pub logs: Option<Vec<LogEntry>>,
+
+    pub schema_version: Option<String>,
+
+    pub biolink_version: Option<String>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: crate::Extensions,
+}
+
+// the CURIEs a knowledge source attribute names, or None if its value is not one or a list of them
+fn source_ids(attribute: &Attribute) -> Option<Vec<CURIE>> {
+    match &attribute.value {
+        Value::String(id) => id.parse().ok().map(|id| vec![id]),
+        Value::Array(ids) => ids.iter().map(|id| id.as_str().and_then(|id| id.parse().ok())).collect(),
+        _ => None,
+    }
+}
+
+fn source_role(attribute_type_id: &CURIE) -> Option<ResourceRoleEnum> {
+    match attribute_type_id.to_string().as_str() {
+        PRIMARY_KNOWLEDGE_SOURCE => Some(ResourceRoleEnum::PrimaryKnowledgeSource),
+        AGGREGATOR_KNOWLEDGE_SOURCE => Some(ResourceRoleEnum::AggregatorKnowledgeSource),
+        SUPPORTING_DATA_SOURCE => Some(ResourceRoleEnum::SupportingDataSource),
+        _ => None,
+    }
+}
+
+/// Takes the knowledge source attributes out of `attributes` and turns them into retrieval sources. Aggregators are
+/// listed as retrieving from the primary source, the only upstream relation the attributes imply.
+pub(crate) fn sources_from_attributes(attributes: &mut Vec<Attribute>) -> Vec<RetrievalSource> {
+    let mut sources: Vec<RetrievalSource> = vec![];
+    attributes.retain(|attribute| match (source_role(&attribute.attribute_type_id), source_ids(attribute)) {
+        (Some(role), Some(ids)) => {
+            sources.extend(ids.into_iter().map(|id| RetrievalSource::new(id, role.clone())));
+            false
+        }
+        _ => true,
+    });
+
+    let primary: Vec<CURIE> = sources
+        .iter()
+        .filter(|s| s.resource_role == ResourceRoleEnum::PrimaryKnowledgeSource)
+        .map(|s| s.resource_id.clone())
+        .collect();
+    if !primary.is_empty() {
+        sources
+            .iter_mut()
+            .filter(|s| s.resource_role == ResourceRoleEnum::AggregatorKnowledgeSource)
+            .for_each(|s| s.upstream_resource_ids = Some(primary.clone()));
+    }
+    sources
+}
+
+/// The knowledge source attributes a pre-1.4 client would look for: the primary source as a single value, the others
+/// as lists.
+pub(crate) fn attributes_from_sources(sources: &[RetrievalSource]) -> Vec<Attribute> {
+    [
+        (ResourceRoleEnum::PrimaryKnowledgeSource, PRIMARY_KNOWLEDGE_SOURCE),
+        (ResourceRoleEnum::AggregatorKnowledgeSource, AGGREGATOR_KNOWLEDGE_SOURCE),
+        (ResourceRoleEnum::SupportingDataSource, SUPPORTING_DATA_SOURCE),
+    ]
+    .into_iter()
+    .filter_map(|(role, attribute_type_id)| {
+        let ids: Vec<Value> = sources
+            .iter()
+            .filter(|s| s.resource_role == role)
+            .map(|s| Value::String(s.resource_id.to_string()))
+            .collect();
+        let value = match (role, ids.len()) {
+            (_, 0) => return None,
+            (ResourceRoleEnum::PrimaryKnowledgeSource, 1) => ids[0].clone(),
+            _ => Value::Array(ids),
+        };
+        Some(Attribute::new(attribute_type_id.parse().expect("knowledge source attribute is a valid CURIE"), value))
+    })
+    .collect()
+}
+
+impl From<QNode> for crate::QNode {
+    /// A 1.4 set node is one whose members are to be considered all together, which 1.5 calls `ALL`.
+    fn from(qnode: QNode) -> Self {
+        crate::QNode {
+            ids: qnode.ids,
+            categories: qnode.categories,
+            set_interpretation: qnode.is_set.filter(|is_set| *is_set).map(|_| SetInterpretationEnum::ALL),
+            member_ids: None,
+            constraints: qnode.constraints,
+            #[cfg(feature = "extensions")]
+            extensions: qnode.extensions,
+        }
+    }
+}
+
+impl From<crate::QNode> for QNode {
+    fn from(qnode: crate::QNode) -> Self {
+        let is_set = match qnode.set_interpretation {
+            Some(SetInterpretationEnum::ALL) | Some(SetInterpretationEnum::MANY) => Some(true),
+            _ => None,
+        };
+        QNode {
+            ids: qnode.ids,
+            categories: qnode.categories,
+            is_set,
+            constraints: qnode.constraints,
+            #[cfg(feature = "extensions")]
+            extensions: qnode.extensions,
+        }
+    }
+}
+
+impl From<QueryGraph> for crate::QueryGraph {
+    fn from(query_graph: QueryGraph) -> Self {
+        crate::QueryGraph {
+            edges: query_graph.edges,
+            nodes: query_graph.nodes.into_iter().map(|(id, qnode)| (id, qnode.into())).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: query_graph.extensions,
+        }
+    }
+}
+
+impl From<crate::QueryGraph> for QueryGraph {
+    fn from(query_graph: crate::QueryGraph) -> Self {
+        QueryGraph {
+            edges: query_graph.edges,
+            nodes: query_graph.nodes.into_iter().map(|(id, qnode)| (id, qnode.into())).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: query_graph.extensions,
+        }
+    }
+}
+
+impl From<Node> for crate::Node {
+    fn from(node: Node) -> Self {
+        crate::Node {
+            name: node.name,
+            categories: node.categories,
+            attributes: node.attributes,
+            is_set: node.is_set,
+            #[cfg(feature = "extensions")]
+            extensions: node.extensions,
+        }
+    }
+}
+
+impl From<crate::Node> for Node {
+    fn from(node: crate::Node) -> Self {
+        Node {
+            name: node.name,
+            categories: node.categories,
+            attributes: node.attributes,
+            is_set: node.is_set,
+            #[cfg(feature = "extensions")]
+            extensions: node.extensions,
+        }
+    }
+}
+
+impl From<Edge> for crate::Edge {
+    /// Knowledge source attributes are always taken out; they become the edge's sources unless it already has some.
+    fn from(edge: Edge) -> Self {
+        let mut attributes = edge.attributes;
+        let from_attributes = attributes.as_mut().map(sources_from_attributes).unwrap_or_default();
+        let sources = match edge.sources {
+            Some(sources) if !sources.is_empty() => sources,
+            _ => from_attributes,
+        };
+        crate::Edge {
+            subject: edge.subject,
+            predicate: edge.predicate,
+            object: edge.object,
+            sources,
+            attributes,
+            qualifiers: edge.qualifiers,
+            #[cfg(feature = "extensions")]
+            extensions: edge.extensions,
+        }
+    }
+}
+
+impl From<crate::Edge> for Edge {
+    /// Keeps `sources` and repeats them as attributes for clients that only know those.
+    fn from(edge: crate::Edge) -> Self {
+        let mut attributes = edge.attributes.unwrap_or_default();
+        let missing: Vec<Attribute> = attributes_from_sources(&edge.sources)
+            .into_iter()
+            .filter(|a| !attributes.iter().any(|existing| existing.attribute_type_id == a.attribute_type_id))
+            .collect();
+        attributes.extend(missing);
+        Edge {
+            subject: edge.subject,
+            predicate: edge.predicate,
+            object: edge.object,
+            sources: Some(edge.sources),
+            attributes: Some(attributes),
+            qualifiers: edge.qualifiers,
+            #[cfg(feature = "extensions")]
+            extensions: edge.extensions,
+        }
+    }
+}
+
+impl From<KnowledgeGraph> for crate::KnowledgeGraph {
+    fn from(knowledge_graph: KnowledgeGraph) -> Self {
+        crate::KnowledgeGraph {
+            nodes: knowledge_graph.nodes.into_iter().map(|(id, node)| (id, node.into())).collect(),
+            edges: knowledge_graph.edges.into_iter().map(|(id, edge)| (id, edge.into())).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: knowledge_graph.extensions,
+        }
+    }
+}
+
+impl From<crate::KnowledgeGraph> for KnowledgeGraph {
+    fn from(knowledge_graph: crate::KnowledgeGraph) -> Self {
+        KnowledgeGraph {
+            nodes: knowledge_graph.nodes.into_iter().map(|(id, node)| (id, node.into())).collect(),
+            edges: knowledge_graph.edges.into_iter().map(|(id, edge)| (id, edge.into())).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: knowledge_graph.extensions,
+        }
+    }
+}
+
+impl From<NodeBinding> for crate::NodeBinding {
+    fn from(binding: NodeBinding) -> Self {
+        crate::NodeBinding {
+            id: binding.id,
+            query_id: binding.query_id,
+            attributes: binding.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: binding.extensions,
+        }
+    }
+}
+
+impl From<crate::NodeBinding> for NodeBinding {
+    fn from(binding: crate::NodeBinding) -> Self {
+        NodeBinding {
+            id: binding.id,
+            query_id: binding.query_id,
+            attributes: binding.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: binding.extensions,
+        }
+    }
+}
+
+impl From<EdgeBinding> for crate::EdgeBinding {
+    fn from(binding: EdgeBinding) -> Self {
+        crate::EdgeBinding {
+            id: binding.id,
+            attributes: binding.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: binding.extensions,
+        }
+    }
+}
+
+impl From<crate::EdgeBinding> for EdgeBinding {
+    fn from(binding: crate::EdgeBinding) -> Self {
+        EdgeBinding {
+            id: binding.id,
+            attributes: binding.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: binding.extensions,
+        }
+    }
+}
+
+// converts every binding in a binding map
+pub(crate) fn convert_bindings<T, U: From<T>>(bindings: BTreeMap<String, Vec<T>>) -> BTreeMap<String, Vec<U>> {
+    bindings.into_iter().map(|(id, list)| (id, list.into_iter().map(U::from).collect())).collect()
+}
+
+impl From<Analysis> for crate::Analysis {
+    fn from(analysis: Analysis) -> Self {
+        crate::Analysis {
+            resource_id: analysis.resource_id,
+            score: analysis.score,
+            scoring_method: analysis.scoring_method,
+            support_graphs: analysis.support_graphs,
+            edge_bindings: convert_bindings(analysis.edge_bindings),
+            attributes: analysis.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: analysis.extensions,
+        }
+    }
+}
+
+impl From<crate::Analysis> for Analysis {
+    fn from(analysis: crate::Analysis) -> Self {
+        Analysis {
+            resource_id: analysis.resource_id,
+            score: analysis.score,
+            scoring_method: analysis.scoring_method,
+            support_graphs: analysis.support_graphs,
+            edge_bindings: convert_bindings(analysis.edge_bindings),
+            attributes: analysis.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: analysis.extensions,
+        }
+    }
+}
+
+impl From<Result> for crate::Result {
+    fn from(result: Result) -> Self {
+        crate::Result {
+            node_bindings: convert_bindings(result.node_bindings),
+            analyses: result.analyses.into_iter().map(crate::Analysis::from).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: result.extensions,
+        }
+    }
+}
+
+impl From<crate::Result> for Result {
+    fn from(result: crate::Result) -> Self {
+        Result {
+            node_bindings: convert_bindings(result.node_bindings),
+            analyses: result.analyses.into_iter().map(Analysis::from).collect(),
+            #[cfg(feature = "extensions")]
+            extensions: result.extensions,
+        }
+    }
+}
+
+impl From<AuxiliaryGraph> for crate::AuxiliaryGraph {
+    fn from(auxiliary_graph: AuxiliaryGraph) -> Self {
+        crate::AuxiliaryGraph {
+            edges: auxiliary_graph.edges,
+            attributes: auxiliary_graph.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: auxiliary_graph.extensions,
+        }
+    }
+}
+
+impl From<crate::AuxiliaryGraph> for AuxiliaryGraph {
+    fn from(auxiliary_graph: crate::AuxiliaryGraph) -> Self {
+        AuxiliaryGraph {
+            edges: auxiliary_graph.edges,
+            attributes: auxiliary_graph.attributes,
+            #[cfg(feature = "extensions")]
+            extensions: auxiliary_graph.extensions,
+        }
+    }
+}
+
+impl From<Message> for crate::Message {
+    fn from(message: Message) -> Self {
+        crate::Message {
+            query_graph: message.query_graph.map(Into::into),
+            knowledge_graph: message.knowledge_graph.map(Into::into),
+            auxiliary_graphs: message.auxiliary_graphs.map(|graphs| graphs.into_iter().map(|(id, graph)| (id, graph.into())).collect()),
+            results: message.results.map(|results| results.into_iter().map(Into::into).collect()),
+            #[cfg(feature = "extensions")]
+            extensions: message.extensions,
+        }
+    }
+}
+
+impl From<crate::Message> for Message {
+    fn from(message: crate::Message) -> Self {
+        Message {
+            query_graph: message.query_graph.map(Into::into),
+            knowledge_graph: message.knowledge_graph.map(Into::into),
+            auxiliary_graphs: message.auxiliary_graphs.map(|graphs| graphs.into_iter().map(|(id, graph)| (id, graph.into())).collect()),
+            results: message.results.map(|results| results.into_iter().map(Into::into).collect()),
+            #[cfg(feature = "extensions")]
+            extensions: message.extensions,
+        }
+    }
+}
+
+impl From<Response> for crate::Response {
+    fn from(response: Response) -> Self {
+        crate::Response {
+            workflow: response.workflow,
+            message: response.message.into(),
+            status: response.status,
+            description: response.description,
+            logs: response.logs,
+            schema_version: response.schema_version,
+            biolink_version: response.biolink_version,
+            #[cfg(feature = "extensions")]
+            extensions: response.extensions,
+        }
+    }
+}
+
+impl From<crate::Response> for Response {
+    fn from(response: crate::Response) -> Self {
+        Response {
+            workflow: response.workflow,
+            message: response.message.into(),
+            status: response.status,
+            description: response.description,
+            logs: response.logs,
+            schema_version: response.schema_version,
+            biolink_version: response.biolink_version,
+            #[cfg(feature = "extensions")]
+            extensions: response.extensions,
+        }
+    }
+}
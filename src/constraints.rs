@@ -0,0 +1,473 @@
+#[cfg(feature = "biolink")]
+use crate::biolink::{BiolinkModel, BiolinkModelError};
+use crate::{Attribute, AttributeConstraint, AttributeConstraintOperator, Edge, QEdge, Qualifier, QualifierConstraint};
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt;
+
+impl fmt::Display for AttributeConstraintOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            AttributeConstraintOperator::Equal => "==",
+            AttributeConstraintOperator::GreaterThan => ">",
+            AttributeConstraintOperator::LessThan => "<",
+            AttributeConstraintOperator::Matches => "matches",
+            AttributeConstraintOperator::StrictEqual => "===",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstraintError {
+    UnsupportedComparison {
+        operator: AttributeConstraintOperator,
+        attribute_value: Value,
+        constraint_value: Value,
+    },
+    InvalidPattern {
+        pattern: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConstraintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintError::UnsupportedComparison {
+                operator,
+                attribute_value,
+                constraint_value,
+            } => write!(f, "cannot evaluate {} {} {}", attribute_value, operator, constraint_value),
+            ConstraintError::InvalidPattern { pattern, message } => write!(f, "invalid pattern {:?}: {}", pattern, message),
+        }
+    }
+}
+
+impl std::error::Error for ConstraintError {}
+
+// a list value is compared item by item, anything else is a single item
+fn items(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(values) => values.iter().collect(),
+        value => vec![value],
+    }
+}
+
+// numbers sent as strings (e.g. "20") compare numerically
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn unsupported(operator: AttributeConstraintOperator, attribute_value: &Value, constraint_value: &Value) -> ConstraintError {
+    ConstraintError::UnsupportedComparison {
+        operator,
+        attribute_value: attribute_value.clone(),
+        constraint_value: constraint_value.clone(),
+    }
+}
+
+fn order(operator: AttributeConstraintOperator, attribute_value: &Value, constraint_value: &Value) -> Result<Option<Ordering>, ConstraintError> {
+    match (as_number(attribute_value), as_number(constraint_value)) {
+        (Some(a), Some(c)) => Ok(a.partial_cmp(&c)),
+        _ => match (attribute_value, constraint_value) {
+            (Value::String(a), Value::String(c)) => Ok(Some(a.cmp(c))),
+            _ => Err(unsupported(operator, attribute_value, constraint_value)),
+        },
+    }
+}
+
+fn is_match(pattern: &Regex, operator: AttributeConstraintOperator, attribute_value: &Value, constraint_value: &Value) -> Result<bool, ConstraintError> {
+    match attribute_value {
+        Value::String(s) => Ok(pattern.is_match(s)),
+        Value::Number(n) => Ok(pattern.is_match(&n.to_string())),
+        Value::Bool(b) => Ok(pattern.is_match(&b.to_string())),
+        _ => Err(unsupported(operator, attribute_value, constraint_value)),
+    }
+}
+
+fn compare(operator: AttributeConstraintOperator, attribute_value: &Value, constraint_value: &Value) -> Result<bool, ConstraintError> {
+    match operator {
+        AttributeConstraintOperator::Equal => Ok(match (as_number(attribute_value), as_number(constraint_value)) {
+            (Some(a), Some(c)) => a == c,
+            _ => attribute_value == constraint_value,
+        }),
+        AttributeConstraintOperator::GreaterThan => Ok(order(operator, attribute_value, constraint_value)? == Some(Ordering::Greater)),
+        AttributeConstraintOperator::LessThan => Ok(order(operator, attribute_value, constraint_value)? == Some(Ordering::Less)),
+        AttributeConstraintOperator::StrictEqual => Ok(attribute_value == constraint_value),
+        // patterns are compiled once by `try_evaluate` and checked with `is_match` instead
+        AttributeConstraintOperator::Matches => Err(unsupported(operator, attribute_value, constraint_value)),
+    }
+}
+
+impl AttributeConstraint {
+    /// Evaluates this constraint against the attributes whose `attribute_type_id` equals `id`.
+    ///
+    /// List-valued attribute and constraint values are compared pairwise and the comparison holds if any pair does,
+    /// except for `===`, which compares whole values without coercion. `not` negates the result. A missing attribute
+    /// never satisfies a constraint. Values are compared as given; `unit_id` and `unit_name` are not used for conversion.
+    /// Values that cannot be compared are skipped, so this only fails when none of them can be.
+    pub fn try_evaluate(&self, attributes: &[Attribute]) -> Result<bool, ConstraintError> {
+        let candidates: Vec<&Attribute> = attributes.iter().filter(|a| a.attribute_type_id == self.id).collect();
+        if candidates.is_empty() {
+            return Ok(false);
+        }
+
+        let patterns = match self.operator {
+            AttributeConstraintOperator::Matches => items(&self.value)
+                .into_iter()
+                .map(|pattern| match pattern.as_str() {
+                    Some(p) => Regex::new(p).map(|regex| (regex, pattern)).map_err(|e| ConstraintError::InvalidPattern {
+                        pattern: p.to_string(),
+                        message: e.to_string(),
+                    }),
+                    None => Err(unsupported(self.operator, &Value::Null, pattern)),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => vec![],
+        };
+
+        // one outcome per pair of attribute and constraint values
+        let mut outcomes: Vec<Result<bool, ConstraintError>> = vec![];
+        for attribute in candidates {
+            if self.operator == AttributeConstraintOperator::StrictEqual {
+                outcomes.push(Ok(attribute.value == self.value));
+                continue;
+            }
+            for attribute_value in items(&attribute.value) {
+                match self.operator {
+                    AttributeConstraintOperator::Matches => patterns
+                        .iter()
+                        .for_each(|(regex, pattern)| outcomes.push(is_match(regex, self.operator, attribute_value, pattern))),
+                    operator => items(&self.value)
+                        .into_iter()
+                        .for_each(|constraint_value| outcomes.push(compare(operator, attribute_value, constraint_value))),
+                }
+            }
+        }
+
+        let mut satisfied = false;
+        let mut compared = false;
+        let mut first_error = None;
+        for outcome in outcomes {
+            match outcome {
+                Ok(holds) => {
+                    compared = true;
+                    satisfied |= holds;
+                }
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        if let (false, Some(error)) = (compared, first_error) {
+            return Err(error);
+        }
+        Ok(satisfied != self.not.unwrap_or(false))
+    }
+
+    /// Like `try_evaluate`, treating unsupported comparisons as unsatisfied.
+    pub fn evaluate(&self, attributes: &[Attribute]) -> bool {
+        self.try_evaluate(attributes).unwrap_or(false)
+    }
+}
+
+/// True as soon as one item passes. An item that cannot be judged only fails the check when no other item passes.
+pub(crate) fn any_of<T, E>(items: impl IntoIterator<Item = T>, mut f: impl FnMut(T) -> Result<bool, E>) -> Result<bool, E> {
+    let mut error = None;
+    for item in items {
+        match f(item) {
+            Ok(true) => return Ok(true),
+            Ok(false) => {}
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    error.map_or(Ok(false), Err)
+}
+
+/// False as soon as one item fails. An item that cannot be judged only fails the check when every other item passes.
+pub(crate) fn all_of<T, E>(items: impl IntoIterator<Item = T>, mut f: impl FnMut(T) -> Result<bool, E>) -> Result<bool, E> {
+    let mut error = None;
+    for item in items {
+        match f(item) {
+            Ok(true) => {}
+            Ok(false) => return Ok(false),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    error.map_or(Ok(true), Err)
+}
+
+fn exact(found: &Qualifier, expected: &Qualifier) -> Result<bool, Infallible> {
+    Ok(found == expected)
+}
+
+impl QualifierConstraint {
+    /// True when every qualifier in `qualifier_set` is present in `qualifiers`.
+    pub fn is_satisfied_by(&self, qualifiers: &[Qualifier]) -> bool {
+        self.is_satisfied_with(qualifiers, &exact).unwrap_or_else(|never| match never {})
+    }
+
+    /// Like `is_satisfied_by`, but a qualifier value also satisfies any broader value of the same qualifier. Fails if
+    /// the answer depends on a qualifier or value the model does not define.
+    #[cfg(feature = "biolink")]
+    pub fn is_satisfied_by_with_model(&self, qualifiers: &[Qualifier], model: &BiolinkModel) -> Result<bool, BiolinkModelError> {
+        self.is_satisfied_with(qualifiers, &|found, expected| qualifier_subsumed_by(found, expected, model))
+    }
+
+    pub(crate) fn is_satisfied_with<E>(&self, qualifiers: &[Qualifier], matches: &dyn Fn(&Qualifier, &Qualifier) -> Result<bool, E>) -> Result<bool, E> {
+        all_of(&self.qualifier_set, |expected| any_of(qualifiers, |found| matches(found, expected)))
+    }
+}
+
+#[cfg(feature = "biolink")]
+pub(crate) fn qualifier_subsumed_by(found: &Qualifier, expected: &Qualifier, model: &BiolinkModel) -> Result<bool, BiolinkModelError> {
+    if found.qualifier_type_id != expected.qualifier_type_id {
+        return Ok(false);
+    }
+    if found.qualifier_value == expected.qualifier_value {
+        return Ok(true);
+    }
+    model.is_qualifier_value_subsumed_by(&found.qualifier_type_id, &found.qualifier_value, &expected.qualifier_value)
+}
+
+// AND within a qualifier_set, OR across qualifier_sets; no (or empty) qualifier_constraints accept any edge
+pub(crate) fn satisfies_qualifier_constraints<E>(
+    constraints: &[QualifierConstraint],
+    qualifiers: &[Qualifier],
+    matches: &dyn Fn(&Qualifier, &Qualifier) -> Result<bool, E>,
+) -> Result<bool, E> {
+    if constraints.is_empty() {
+        return Ok(true);
+    }
+    any_of(constraints, |constraint| constraint.is_satisfied_with(qualifiers, matches))
+}
+
+impl QEdge {
+    /// Evaluates `qualifier_constraints` against `edge.qualifiers`: the edge must carry every qualifier of at least one
+    /// `qualifier_set`.
+    pub fn satisfies_qualifier_constraints(&self, edge: &Edge) -> bool {
+        satisfies_qualifier_constraints(
+            self.qualifier_constraints.as_deref().unwrap_or_default(),
+            edge.qualifiers.as_deref().unwrap_or_default(),
+            &exact,
+        )
+        .unwrap_or_else(|never| match never {})
+    }
+
+    /// Like `satisfies_qualifier_constraints`, but understands qualifier value hierarchies, so an `object_aspect_qualifier`
+    /// of `activity` satisfies a constraint on `activity_or_abundance`. Fails if the answer depends on a qualifier or
+    /// value the model does not define.
+    #[cfg(feature = "biolink")]
+    pub fn satisfies_qualifier_constraints_with_model(&self, edge: &Edge, model: &BiolinkModel) -> Result<bool, BiolinkModelError> {
+        satisfies_qualifier_constraints(
+            self.qualifier_constraints.as_deref().unwrap_or_default(),
+            edge.qualifiers.as_deref().unwrap_or_default(),
+            &|found, expected| qualifier_subsumed_by(found, expected, model),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::constraints::ConstraintError;
+    use crate::{Attribute, AttributeConstraint, AttributeConstraintOperator, Edge, QEdge};
+    use serde_json::{json, Value};
+
+    fn constraint(operator: AttributeConstraintOperator, value: Value) -> AttributeConstraint {
+        AttributeConstraint::new("biolink:evidence_count".parse().unwrap(), "evidence_count".to_string(), operator, value)
+    }
+
+    fn attributes(value: Value) -> Vec<Attribute> {
+        vec![
+            Attribute::new("biolink:publications".parse().unwrap(), json!(["PMID:1"])),
+            Attribute::new("biolink:evidence_count".parse().unwrap(), value),
+        ]
+    }
+
+    #[test]
+    fn numeric_coercion() {
+        let gt = constraint(AttributeConstraintOperator::GreaterThan, json!("20"));
+        assert!(gt.evaluate(&attributes(json!(25))));
+        assert!(!gt.evaluate(&attributes(json!(20))));
+        assert!(gt.evaluate(&attributes(json!("21.5"))));
+
+        let lt = constraint(AttributeConstraintOperator::LessThan, json!(20));
+        assert!(lt.evaluate(&attributes(json!(3))));
+
+        let eq = constraint(AttributeConstraintOperator::Equal, json!("20"));
+        assert!(eq.evaluate(&attributes(json!(20))));
+        assert!(eq.evaluate(&attributes(json!(20.0))));
+
+        let strict = constraint(AttributeConstraintOperator::StrictEqual, json!("20"));
+        assert!(!strict.evaluate(&attributes(json!(20))));
+        assert!(strict.evaluate(&attributes(json!("20"))));
+    }
+
+    #[test]
+    fn list_values() {
+        let gt = constraint(AttributeConstraintOperator::GreaterThan, json!(20));
+        assert!(gt.evaluate(&attributes(json!([3, 25]))));
+        assert!(!gt.evaluate(&attributes(json!([3, 7]))));
+
+        let eq = constraint(AttributeConstraintOperator::Equal, json!([1, 7]));
+        assert!(eq.evaluate(&attributes(json!([3, 7]))));
+
+        let strict = constraint(AttributeConstraintOperator::StrictEqual, json!([3, 7]));
+        assert!(strict.evaluate(&attributes(json!([3, 7]))));
+        assert!(!strict.evaluate(&attributes(json!([7, 3]))));
+    }
+
+    #[test]
+    fn matches_and_negation() {
+        let matches = constraint(AttributeConstraintOperator::Matches, json!("^infores:"));
+        assert!(matches.evaluate(&attributes(json!("infores:kp0"))));
+        assert!(!matches.evaluate(&attributes(json!("kp0"))));
+
+        let mut not_matches = matches.clone();
+        not_matches.not = Some(true);
+        assert!(!not_matches.evaluate(&attributes(json!("infores:kp0"))));
+        assert!(not_matches.evaluate(&attributes(json!("kp0"))));
+
+        // a missing attribute never satisfies the constraint, negated or not
+        assert!(!not_matches.evaluate(&[]));
+    }
+
+    #[test]
+    fn unsupported_combinations() {
+        let gt = constraint(AttributeConstraintOperator::GreaterThan, json!(20));
+        assert!(matches!(
+            gt.try_evaluate(&attributes(json!({"count": 25}))),
+            Err(ConstraintError::UnsupportedComparison { .. })
+        ));
+        assert!(matches!(gt.try_evaluate(&attributes(json!(true))), Err(ConstraintError::UnsupportedComparison { .. })));
+        assert!(!gt.evaluate(&attributes(json!(true))));
+
+        let matches = constraint(AttributeConstraintOperator::Matches, json!("(unclosed"));
+        let error = matches.try_evaluate(&attributes(json!("anything"))).unwrap_err();
+        assert!(matches!(error, ConstraintError::InvalidPattern { .. }));
+    }
+
+    #[test]
+    fn skips_values_that_cannot_be_compared() {
+        let gt = constraint(AttributeConstraintOperator::GreaterThan, json!(20));
+        let mut found = attributes(json!({"count": 25}));
+        found.push(Attribute::new("biolink:evidence_count".parse().unwrap(), json!(25)));
+        assert_eq!(gt.try_evaluate(&found), Ok(true));
+
+        found[2].value = json!(3);
+        assert_eq!(gt.try_evaluate(&found), Ok(false));
+        assert_eq!(gt.try_evaluate(&attributes(json!([true, 21]))), Ok(true));
+    }
+
+    #[test]
+    fn operator_serde() {
+        let constraint: AttributeConstraint = serde_json::from_str(r#"{ "name": "evidence_count gt 20", "id": "biolink:evidence_count", "operator": ">", "value": "20"}"#).unwrap();
+        assert_eq!(constraint.operator, AttributeConstraintOperator::GreaterThan);
+        assert_eq!(constraint.operator.to_string(), ">");
+        assert!(serde_json::from_str::<AttributeConstraint>(r#"{ "name": "x", "id": "biolink:x", "operator": "!=", "value": 1}"#).is_err());
+    }
+
+    fn qualified(qualifier_constraints: &str, qualifiers: &str) -> (QEdge, Edge) {
+        let qedge = serde_json::from_str(&format!(r#"{{ "subject": "n0", "object": "n1", "qualifier_constraints": {} }}"#, qualifier_constraints)).unwrap();
+        let edge = serde_json::from_str(&format!(
+            r#"{{ "subject": "CHEBI:6801", "predicate": "biolink:affects", "object": "NCBIGene:2645", "sources": [], "qualifiers": {} }}"#,
+            qualifiers
+        ))
+        .unwrap();
+        (qedge, edge)
+    }
+
+    #[test]
+    fn qualifier_set_semantics() {
+        let constraints = r#"[
+            { "qualifier_set": [
+                { "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" },
+                { "qualifier_type_id": "biolink:object_direction_qualifier", "qualifier_value": "decreased" }
+            ] },
+            { "qualifier_set": [
+                { "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "abundance" }
+            ] }
+        ]"#;
+
+        let (qedge, edge) = qualified(
+            constraints,
+            r#"[
+                { "qualifier_type_id": "biolink:object_direction_qualifier", "qualifier_value": "decreased" },
+                { "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" },
+                { "qualifier_type_id": "biolink:species_context_qualifier", "qualifier_value": "NCBITaxon:9606" }
+            ]"#,
+        );
+        assert!(qedge.satisfies_qualifier_constraints(&edge));
+
+        // only half of the first set
+        let (qedge, edge) = qualified(
+            constraints,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" }]"#,
+        );
+        assert!(!qedge.satisfies_qualifier_constraints(&edge));
+
+        // the second set on its own
+        let (qedge, edge) = qualified(
+            constraints,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "abundance" }]"#,
+        );
+        assert!(qedge.satisfies_qualifier_constraints(&edge));
+
+        let (qedge, edge) = qualified("[]", "[]");
+        assert!(qedge.satisfies_qualifier_constraints(&edge));
+        let (qedge, edge) = qualified(constraints, "[]");
+        assert!(!qedge.satisfies_qualifier_constraints(&edge));
+    }
+
+    #[cfg(feature = "biolink")]
+    #[test]
+    fn qualifier_value_hierarchy() {
+        use crate::biolink::BiolinkModel;
+
+        let (qedge, edge) = qualified(
+            r#"[{ "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity_or_abundance" }] }]"#,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" }]"#,
+        );
+        assert!(!qedge.satisfies_qualifier_constraints(&edge));
+        assert!(qedge.satisfies_qualifier_constraints_with_model(&edge, BiolinkModel::bundled_subset()).unwrap());
+
+        // the hierarchy only applies within the same qualifier
+        let (qedge, edge) = qualified(
+            r#"[{ "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity_or_abundance" }] }]"#,
+            r#"[{ "qualifier_type_id": "biolink:subject_aspect_qualifier", "qualifier_value": "activity" }]"#,
+        );
+        assert!(!qedge.satisfies_qualifier_constraints_with_model(&edge, BiolinkModel::bundled_subset()).unwrap());
+
+        let (qedge, edge) = qualified(
+            r#"[{ "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" }] }]"#,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity_or_abundance" }]"#,
+        );
+        assert!(!qedge.satisfies_qualifier_constraints_with_model(&edge, BiolinkModel::bundled_subset()).unwrap());
+
+        // a value the model does not define cannot be judged, unless another qualifier set is satisfied anyway
+        let (qedge, edge) = qualified(
+            r#"[{ "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity_or_abundance" }] }]"#,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "glycosylation" }]"#,
+        );
+        assert!(qedge.satisfies_qualifier_constraints_with_model(&edge, BiolinkModel::bundled_subset()).is_err());
+        let (qedge, edge) = qualified(
+            r#"[
+                { "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity_or_abundance" }] },
+                { "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "glycosylation" }] }
+            ]"#,
+            r#"[{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "glycosylation" }]"#,
+        );
+        assert!(qedge.satisfies_qualifier_constraints_with_model(&edge, BiolinkModel::bundled_subset()).unwrap());
+    }
+}
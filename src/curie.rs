@@ -0,0 +1,351 @@
+use crate::validation::{biolink_entity_regex, biolink_predicate_regex, curie_regex, BIOLINK_ENTITY_PATTERN, BIOLINK_PREDICATE_PATTERN, CURIE_PATTERN};
+use rocket_okapi::okapi::schemars::gen::SchemaGenerator;
+use rocket_okapi::okapi::schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use rocket_okapi::okapi::schemars::JsonSchema;
+use serde::de::{self, Deserialize, Deserializer};
+use serde_with::SerializeDisplay;
+use std::borrow::Borrow;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCurieError {
+    pub expected: &'static str,
+
+    pub value: String,
+}
+
+impl fmt::Display for ParseCurieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a valid {}", self.value, self.expected)
+    }
+}
+
+impl std::error::Error for ParseCurieError {}
+
+thread_local! {
+    static LENIENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` with the CURIE checks switched off, so that anything deserialized in it keeps malformed CURIEs, categories
+/// and predicates as they are instead of failing on the first one.
+pub(crate) fn lenient<T, F: FnOnce() -> T>(f: F) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LENIENT.with(|lenient| lenient.set(self.0));
+        }
+    }
+
+    let _restore = Restore(LENIENT.with(|lenient| lenient.replace(true)));
+    f()
+}
+
+/// A compact URI of the form `prefix:local_id`, e.g. `MONDO:0005737`.
+///
+/// The default CURIE is empty, and so not valid; `validate` reports it like any other malformed CURIE.
+#[derive(Clone, Default, SerializeDisplay)]
+pub struct CURIE {
+    value: String,
+    separator: usize,
+}
+
+impl CURIE {
+    pub fn new(prefix: &str, local_id: &str) -> Result<CURIE, ParseCurieError> {
+        format!("{}:{}", prefix, local_id).parse()
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.value[..self.separator]
+    }
+
+    pub fn local_id(&self) -> &str {
+        self.value.get(self.separator + 1..).unwrap_or_default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    // a value that has not been checked; without a separator the whole value is the prefix
+    fn unchecked(value: String) -> CURIE {
+        let separator = value.find(':').unwrap_or(value.len());
+        CURIE { value, separator }
+    }
+}
+
+impl FromStr for CURIE {
+    type Err = ParseCurieError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find(':') {
+            Some(separator) if curie_regex().is_match(s) => Ok(CURIE { value: s.to_string(), separator }),
+            _ => Err(ParseCurieError {
+                expected: "CURIE",
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// A biolink class CURIE, e.g. `biolink:Disease`.
+#[derive(Clone, Default, SerializeDisplay)]
+pub struct BiolinkEntity(CURIE);
+
+impl BiolinkEntity {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for BiolinkEntity {
+    type Err = ParseCurieError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if biolink_entity_regex().is_match(s) {
+            Ok(BiolinkEntity(s.parse()?))
+        } else {
+            Err(ParseCurieError {
+                expected: "biolink entity",
+                value: s.to_string(),
+            })
+        }
+    }
+}
+
+/// A biolink predicate CURIE, e.g. `biolink:treats`.
+#[derive(Clone, Default, SerializeDisplay)]
+pub struct BiolinkPredicate(CURIE);
+
+impl BiolinkPredicate {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl FromStr for BiolinkPredicate {
+    type Err = ParseCurieError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if biolink_predicate_regex().is_match(s) {
+            Ok(BiolinkPredicate(s.parse()?))
+        } else {
+            Err(ParseCurieError {
+                expected: "biolink predicate",
+                value: s.to_string(),
+            })
+        }
+    }
+}
+
+macro_rules! biolink_curie {
+    ($name:ident) => {
+        impl $name {
+            fn unchecked(value: String) -> $name {
+                $name(CURIE::unchecked(value))
+            }
+        }
+
+        impl Deref for $name {
+            type Target = CURIE;
+
+            fn deref(&self) -> &CURIE {
+                &self.0
+            }
+        }
+
+        impl From<$name> for CURIE {
+            fn from(value: $name) -> CURIE {
+                value.0
+            }
+        }
+    };
+}
+
+biolink_curie!(BiolinkEntity);
+biolink_curie!(BiolinkPredicate);
+
+macro_rules! curie_common {
+    ($name:ident, $pattern:expr) => {
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.as_str()).finish()
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.as_str().cmp(other.as_str())
+            }
+        }
+
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.as_str().hash(state)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                if LENIENT.with(Cell::get) {
+                    Ok($name::unchecked(value))
+                } else {
+                    value.parse().map_err(de::Error::custom)
+                }
+            }
+        }
+
+        impl JsonSchema for $name {
+            fn schema_name() -> String {
+                stringify!($name).to_string()
+            }
+
+            fn json_schema(_: &mut SchemaGenerator) -> Schema {
+                SchemaObject {
+                    instance_type: Some(InstanceType::String.into()),
+                    string: Some(Box::new(StringValidation {
+                        pattern: Some($pattern.to_string()),
+                        ..Default::default()
+                    })),
+                    ..Default::default()
+                }
+                .into()
+            }
+        }
+    };
+}
+
+curie_common!(CURIE, CURIE_PATTERN);
+curie_common!(BiolinkEntity, BIOLINK_ENTITY_PATTERN);
+curie_common!(BiolinkPredicate, BIOLINK_PREDICATE_PATTERN);
+
+#[cfg(test)]
+mod test {
+    use crate::{BiolinkEntity, BiolinkPredicate, Node, CURIE};
+    use rocket_okapi::okapi::schemars::schema_for;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn parse_curie() {
+        let curie: CURIE = "MONDO:0005737".parse().unwrap();
+        assert_eq!(curie.prefix(), "MONDO");
+        assert_eq!(curie.local_id(), "0005737");
+        assert_eq!(curie.to_string(), "MONDO:0005737");
+        assert_eq!(CURIE::new("MONDO", "0005737").unwrap(), curie);
+
+        let curie: CURIE = "ncbi.taxon:9606:extra".parse().unwrap();
+        assert_eq!(curie.prefix(), "ncbi.taxon");
+        assert_eq!(curie.local_id(), "9606:extra");
+
+        assert!("donkey".parse::<CURIE>().is_err());
+        assert!("frizzle chicken".parse::<CURIE>().is_err());
+        assert!(":0005737".parse::<CURIE>().is_err());
+        assert!("MONDO:".parse::<CURIE>().is_err());
+    }
+
+    #[test]
+    fn default_curie() {
+        let curie = CURIE::default();
+        assert_eq!(curie.as_str(), "");
+        assert_eq!(curie.prefix(), "");
+        assert_eq!(curie.local_id(), "");
+
+        let curies: Vec<CURIE> = crate::validation::from_str_lenient(r#"["donkey", "MONDO:0005737"]"#).unwrap();
+        assert_eq!(curies[0].prefix(), "donkey");
+        assert_eq!(curies[0].local_id(), "");
+        assert_eq!(curies[1].local_id(), "0005737");
+    }
+
+    #[test]
+    fn parse_biolink() {
+        let entity: BiolinkEntity = "biolink:ChemicalEntity".parse().unwrap();
+        assert_eq!(entity.prefix(), "biolink");
+        assert_eq!(entity.local_id(), "ChemicalEntity");
+        assert!("biolink:treats".parse::<BiolinkEntity>().is_err());
+        assert!("poopy pants".parse::<BiolinkEntity>().is_err());
+
+        let predicate: BiolinkPredicate = "biolink:treats".parse().unwrap();
+        assert_eq!(predicate, "biolink:treats");
+        assert!("biolink:ChemicalEntity".parse::<BiolinkPredicate>().is_err());
+        assert!("RO:0002434".parse::<BiolinkPredicate>().is_err());
+    }
+
+    #[test]
+    fn serde_representation() {
+        let curies: Vec<CURIE> = serde_json::from_str(r#"["MONDO:0009061", "MONDO:0004979"]"#).unwrap();
+        assert_eq!(serde_json::to_string(&curies).unwrap(), r#"["MONDO:0009061","MONDO:0004979"]"#);
+
+        let error = serde_json::from_str::<Vec<BiolinkPredicate>>(r#"["biolink:treats", "biolink:Gene"]"#).unwrap_err();
+        assert!(error.to_string().contains("\"biolink:Gene\" is not a valid biolink predicate"));
+    }
+
+    #[test]
+    fn ordering() {
+        let categories: BTreeSet<BiolinkEntity> = ["biolink:Protein", "biolink:Gene", "biolink:Disease"].iter().map(|c| c.parse().unwrap()).collect();
+        let sorted: Vec<&str> = categories.iter().map(|c| c.as_str()).collect();
+        assert_eq!(sorted, vec!["biolink:Disease", "biolink:Gene", "biolink:Protein"]);
+    }
+
+    #[test]
+    fn schema_patterns() {
+        let schema = serde_json::to_value(schema_for!(Node)).unwrap();
+        assert_eq!(schema["definitions"]["BiolinkEntity"]["pattern"], r"^biolink:[A-Z][a-zA-Z]*$");
+        assert_eq!(schema["definitions"]["CURIE"]["pattern"], crate::validation::CURIE_PATTERN);
+    }
+}
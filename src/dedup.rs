@@ -0,0 +1,223 @@
+use crate::merge::merge_edge_binding;
+use crate::{merge_edge_attributes, merge_edge_sources, BiolinkPredicate, Edge, EdgeBinding, KnowledgeGraph, Message, ResourceRoleEnum, CURIE};
+use std::collections::{BTreeMap, HashMap};
+
+/// What makes two knowledge graph edges the same statement, regardless of the ids KPs gave them.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EdgeIdentity {
+    pub subject: CURIE,
+
+    pub predicate: BiolinkPredicate,
+
+    pub object: CURIE,
+
+    /// (qualifier_type_id, qualifier_value), sorted.
+    pub qualifiers: Vec<(CURIE, String)>,
+
+    pub primary_source: Option<CURIE>,
+}
+
+impl Edge {
+    pub fn identity(&self) -> EdgeIdentity {
+        let mut qualifiers: Vec<(CURIE, String)> = self.qualifiers.iter().flatten().map(|q| (q.qualifier_type_id.clone(), q.qualifier_value.clone())).collect();
+        qualifiers.sort();
+        qualifiers.dedup();
+        EdgeIdentity {
+            subject: self.subject.clone(),
+            predicate: self.predicate.clone(),
+            object: self.object.clone(),
+            qualifiers,
+            primary_source: self
+                .sources
+                .iter()
+                .find(|s| s.resource_role == ResourceRoleEnum::PrimaryKnowledgeSource)
+                .map(|s| s.resource_id.clone()),
+        }
+    }
+}
+
+impl KnowledgeGraph {
+    /// Collapses edges with the same `EdgeIdentity` into the one with the smallest id, merging their sources,
+    /// attributes and extensions. Returns the ids that were removed, mapped to the id that replaced them.
+    pub fn dedup_edges(&mut self) -> BTreeMap<String, String> {
+        let mut ids: Vec<String> = self.edges.keys().cloned().collect();
+        ids.sort();
+
+        let mut kept: HashMap<EdgeIdentity, String> = HashMap::new();
+        let mut remap = BTreeMap::new();
+        for id in ids {
+            let identity = self.edges[&id].identity();
+            match kept.get(&identity) {
+                Some(kept_id) => {
+                    let duplicate = self.edges.remove(&id).expect("edge ids were just collected");
+                    let edge = self.edges.get_mut(kept_id).expect("kept edges are never removed");
+                    merge_edge_sources(&mut edge.sources, duplicate.sources);
+                    merge_edge_attributes(&mut edge.attributes, duplicate.attributes);
+                    #[cfg(feature = "extensions")]
+                    crate::merge_extensions(&mut edge.extensions, duplicate.extensions);
+                    remap.insert(id, kept_id.clone());
+                }
+                None => {
+                    kept.insert(identity, id);
+                }
+            }
+        }
+        remap
+    }
+}
+
+fn remap_edge_bindings(bindings: &mut Vec<EdgeBinding>, remap: &BTreeMap<String, String>) {
+    let mut remapped: Vec<EdgeBinding> = vec![];
+    for mut binding in bindings.drain(..) {
+        if let Some(id) = remap.get(&binding.id) {
+            binding.id = id.clone();
+        }
+        merge_edge_binding(&mut remapped, binding);
+    }
+    *bindings = remapped;
+}
+
+impl Message {
+    /// Deduplicates the knowledge graph edges and rewrites edge bindings and auxiliary graphs to the surviving ids.
+    pub fn dedup_edges(&mut self) -> BTreeMap<String, String> {
+        let remap = match self.knowledge_graph.as_mut() {
+            Some(knowledge_graph) => knowledge_graph.dedup_edges(),
+            None => return BTreeMap::new(),
+        };
+        self.remap_edge_ids(&remap);
+        remap
+    }
+
+    /// Rewrites every `EdgeBinding` and `AuxiliaryGraph::edges` entry found in `remap`; bindings or auxiliary graph
+    /// edges that end up with the same id are collapsed.
+    pub fn remap_edge_ids(&mut self, remap: &BTreeMap<String, String>) {
+        if remap.is_empty() {
+            return;
+        }
+
+        self.results.iter_mut().flatten().flat_map(|r| r.analyses.iter_mut()).for_each(|analysis| {
+            analysis.edge_bindings.values_mut().for_each(|bindings| remap_edge_bindings(bindings, remap));
+        });
+
+        self.auxiliary_graphs.iter_mut().flat_map(|ags| ags.values_mut()).for_each(|auxiliary_graph| {
+            let mut edges: Vec<String> = vec![];
+            for edge in auxiliary_graph.edges.drain(..) {
+                let edge = remap.get(&edge).cloned().unwrap_or(edge);
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+            auxiliary_graph.edges = edges;
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Message;
+
+    #[test]
+    fn dedup_edges() {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {},
+                "edges": {
+                    "kp1-e0": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [
+                            { "resource_id": "infores:drugcentral", "resource_role": "primary_knowledge_source" },
+                            { "resource_id": "infores:kp1", "resource_role": "aggregator_knowledge_source" }
+                        ],
+                        "attributes": [{ "attribute_type_id": "biolink:publications", "value": ["PMID:1"] }]
+                    },
+                    "kp0-e7": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [
+                            { "resource_id": "infores:kp0", "resource_role": "aggregator_knowledge_source" },
+                            { "resource_id": "infores:drugcentral", "resource_role": "primary_knowledge_source" }
+                        ],
+                        "attributes": [{ "attribute_type_id": "biolink:evidence_count", "value": 3 }]
+                    },
+                    "kp2-e1": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [{ "resource_id": "infores:semmeddb", "resource_role": "primary_knowledge_source" }]
+                    },
+                    "kp2-e2": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [{ "resource_id": "infores:drugcentral", "resource_role": "primary_knowledge_source" }],
+                        "qualifiers": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" }]
+                    }
+                }
+            },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:ars", "edge_bindings": { "e0": [{ "id": "kp1-e0", "attributes": [] }, { "id": "kp0-e7", "attributes": [] }, { "id": "kp2-e1", "attributes": [] }] } }]
+            }],
+            "auxiliary_graphs": { "ag1": { "edges": ["kp0-e7", "kp1-e0", "kp2-e2"], "attributes": [] } }
+        }"#;
+
+        let mut message: Message = serde_json::from_str(data).unwrap();
+        let remap = message.dedup_edges();
+        assert_eq!(remap.len(), 1);
+        assert_eq!(remap["kp1-e0"], "kp0-e7");
+
+        let kg = message.knowledge_graph.as_ref().unwrap();
+        assert_eq!(kg.edges.len(), 3);
+        let edge = &kg.edges["kp0-e7"];
+        assert_eq!(edge.sources.len(), 3);
+        assert_eq!(edge.attributes.as_ref().unwrap().len(), 2);
+
+        let bindings = &message.results.as_ref().unwrap()[0].analyses[0].edge_bindings["e0"];
+        let ids: Vec<&str> = bindings.iter().map(|eb| eb.id.as_str()).collect();
+        assert_eq!(ids, vec!["kp0-e7", "kp2-e1"]);
+        assert_eq!(message.auxiliary_graphs.unwrap()["ag1"].edges, vec!["kp0-e7", "kp2-e2"]);
+    }
+
+    #[test]
+    fn dedup_edges_with_mixed_attributes() {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {},
+                "edges": {
+                    "e0": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [{ "resource_id": "infores:drugcentral", "resource_role": "primary_knowledge_source" }],
+                        "attributes": [
+                            { "attribute_type_id": "biolink:publications", "original_attribute_name": "pubs", "value": ["PMID:1"] },
+                            { "attribute_type_id": "biolink:evidence_count", "value": 3 },
+                            { "attribute_type_id": "biolink:knowledge_level", "value": "knowledge_assertion" }
+                        ]
+                    },
+                    "e1": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [{ "resource_id": "infores:drugcentral", "resource_role": "primary_knowledge_source" }],
+                        "attributes": [
+                            { "attribute_type_id": "biolink:evidence_count", "value": 3 },
+                            { "attribute_type_id": "biolink:publications", "value": ["PMID:2"] },
+                            { "attribute_type_id": "biolink:publications", "original_attribute_name": "pubs", "value": ["PMID:1"] }
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let mut message: Message = serde_json::from_str(data).unwrap();
+        assert_eq!(message.dedup_edges().len(), 1);
+        let kg = message.knowledge_graph.as_ref().unwrap();
+        let attributes: Vec<(&str, Option<&str>)> = kg.edges["e0"]
+            .attributes
+            .iter()
+            .flatten()
+            .map(|a| (a.attribute_type_id.as_str(), a.original_attribute_name.as_deref()))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                ("biolink:evidence_count", None),
+                ("biolink:knowledge_level", None),
+                ("biolink:publications", None),
+                ("biolink:publications", Some("pubs"))
+            ]
+        );
+    }
+}
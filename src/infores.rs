@@ -0,0 +1,404 @@
+use crate::validation::escape_pointer_token;
+use crate::{Edge, Message, CURIE};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum InforesError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for InforesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InforesError::Io(e) => write!(f, "could not read infores registry: {}", e),
+            InforesError::Json(e) => write!(f, "could not parse infores registry: {}", e),
+            InforesError::Yaml(e) => write!(f, "could not parse infores registry: {}", e),
+            InforesError::UnsupportedFormat(path) => write!(f, "{} is neither a .json nor a .yaml file", path),
+        }
+    }
+}
+
+impl std::error::Error for InforesError {}
+
+impl From<std::io::Error> for InforesError {
+    fn from(e: std::io::Error) -> Self {
+        InforesError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for InforesError {
+    fn from(e: serde_json::Error) -> Self {
+        InforesError::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for InforesError {
+    fn from(e: serde_yaml::Error) -> Self {
+        InforesError::Yaml(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InforesStatus {
+    Released,
+    Deprecated,
+    Draft,
+    Modified,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KnowledgeLevel {
+    KnowledgeAssertion,
+    LogicalEntailment,
+    Prediction,
+    StatisticalAssociation,
+    Observation,
+    NotProvided,
+    Mixed,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentType {
+    ManualAgent,
+    AutomatedAgent,
+    DataAnalysisPipeline,
+    ComputationalModel,
+    TextMiningAgent,
+    ImageProcessingAgent,
+    ManualValidationOfAutomatedAgent,
+    NotProvided,
+    Mixed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// An entry of the Information Resource Registry; fields the registry has but this crate does not use are ignored, and
+/// enum values it does not know become `Unknown` so that one new value does not stop the registry from loading.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct InformationResource {
+    pub id: CURIE,
+
+    pub name: Option<String>,
+
+    pub status: Option<InforesStatus>,
+
+    pub description: Option<String>,
+
+    pub knowledge_level: Option<KnowledgeLevel>,
+
+    pub agent_type: Option<AgentType>,
+
+    #[serde(default)]
+    pub xref: Vec<String>,
+}
+
+impl InformationResource {
+    pub fn is_deprecated(&self) -> bool {
+        self.status == Some(InforesStatus::Deprecated)
+    }
+}
+
+// the registry's own layout, or a bare list of resources
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Snapshot {
+    Catalog { information_resources: Vec<InformationResource> },
+    List(Vec<InformationResource>),
+}
+
+impl Snapshot {
+    fn into_resources(self) -> Vec<InformationResource> {
+        match self {
+            Snapshot::Catalog { information_resources } => information_resources,
+            Snapshot::List(resources) => resources,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InforesProblem {
+    Unknown,
+    Deprecated,
+}
+
+impl fmt::Display for InforesProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InforesProblem::Unknown => write!(f, "unknown information resource"),
+            InforesProblem::Deprecated => write!(f, "deprecated information resource"),
+        }
+    }
+}
+
+/// A resource id that is not a current registry entry, located by a JSON pointer (RFC 6901) relative to the `Message`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnresolvedResource {
+    pub path: String,
+
+    pub resource_id: CURIE,
+
+    pub problem: InforesProblem,
+}
+
+impl fmt::Display for UnresolvedResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.path, self.problem, self.resource_id)
+    }
+}
+
+/// A local snapshot of the Information Resource Registry, keyed by infores CURIE.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InforesRegistry {
+    resources: BTreeMap<CURIE, InformationResource>,
+}
+
+impl InforesRegistry {
+    pub fn new() -> InforesRegistry {
+        InforesRegistry::default()
+    }
+
+    fn from_snapshot(snapshot: Snapshot) -> InforesRegistry {
+        let mut registry = InforesRegistry::new();
+        snapshot.into_resources().into_iter().for_each(|resource| registry.insert(resource));
+        registry
+    }
+
+    /// Reads either the registry's `information_resources` document or a plain list of resources.
+    pub fn from_json<R: Read>(reader: R) -> Result<InforesRegistry, InforesError> {
+        Ok(InforesRegistry::from_snapshot(serde_json::from_reader(reader)?))
+    }
+
+    pub fn from_yaml<R: Read>(reader: R) -> Result<InforesRegistry, InforesError> {
+        Ok(InforesRegistry::from_snapshot(serde_yaml::from_reader(reader)?))
+    }
+
+    /// Loads a `.json`, `.yaml` or `.yml` file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<InforesRegistry, InforesError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => InforesRegistry::from_json(BufReader::new(File::open(path)?)),
+            Some("yaml") | Some("yml") => InforesRegistry::from_yaml(BufReader::new(File::open(path)?)),
+            _ => Err(InforesError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    pub fn insert(&mut self, resource: InformationResource) {
+        self.resources.insert(resource.id.clone(), resource);
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    pub fn get(&self, resource_id: &CURIE) -> Option<&InformationResource> {
+        self.resources.get(resource_id)
+    }
+
+    pub fn name(&self, resource_id: &CURIE) -> Option<&str> {
+        self.get(resource_id).and_then(|r| r.name.as_deref())
+    }
+
+    pub fn knowledge_level(&self, resource_id: &CURIE) -> Option<&KnowledgeLevel> {
+        self.get(resource_id).and_then(|r| r.knowledge_level.as_ref())
+    }
+
+    pub fn agent_type(&self, resource_id: &CURIE) -> Option<&AgentType> {
+        self.get(resource_id).and_then(|r| r.agent_type.as_ref())
+    }
+
+    /// The registry entry for `resource_id`, provided it exists and is not deprecated.
+    pub fn resolve(&self, resource_id: &CURIE) -> Result<&InformationResource, InforesProblem> {
+        match self.get(resource_id) {
+            None => Err(InforesProblem::Unknown),
+            Some(resource) if resource.is_deprecated() => Err(InforesProblem::Deprecated),
+            Some(resource) => Ok(resource),
+        }
+    }
+
+    /// The resources an edge cites, as sources or as their upstream resources, that do not resolve.
+    pub fn check_edge(&self, edge: &Edge) -> Vec<(CURIE, InforesProblem)> {
+        self.edge_problems(edge).into_iter().map(|(_, id, problem)| (id.clone(), problem)).collect()
+    }
+
+    // (path below the edge, resource id, problem)
+    fn edge_problems<'a>(&self, edge: &'a Edge) -> Vec<(String, &'a CURIE, InforesProblem)> {
+        let mut problems = vec![];
+        for (i, source) in edge.sources.iter().enumerate() {
+            if let Err(problem) = self.resolve(&source.resource_id) {
+                problems.push((format!("/sources/{}/resource_id", i), &source.resource_id, problem));
+            }
+            for (j, upstream_id) in source.upstream_resource_ids.iter().flatten().enumerate() {
+                if let Err(problem) = self.resolve(upstream_id) {
+                    problems.push((format!("/sources/{}/upstream_resource_ids/{}", i, j), upstream_id, problem));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Every knowledge graph edge source and analysis `resource_id` in `message` that does not resolve.
+    pub fn check_message(&self, message: &Message) -> Vec<UnresolvedResource> {
+        let mut unresolved = vec![];
+        if let Some(knowledge_graph) = &message.knowledge_graph {
+            let mut edge_ids: Vec<&String> = knowledge_graph.edges.keys().collect();
+            edge_ids.sort();
+            for edge_id in edge_ids {
+                for (path, resource_id, problem) in self.edge_problems(&knowledge_graph.edges[edge_id]) {
+                    unresolved.push(UnresolvedResource {
+                        path: format!("/knowledge_graph/edges/{}{}", escape_pointer_token(edge_id), path),
+                        resource_id: resource_id.clone(),
+                        problem,
+                    });
+                }
+            }
+        }
+        for (i, result) in message.results.iter().flatten().enumerate() {
+            for (j, analysis) in result.analyses.iter().enumerate() {
+                if let Err(problem) = self.resolve(&analysis.resource_id) {
+                    unresolved.push(UnresolvedResource {
+                        path: format!("/results/{}/analyses/{}/resource_id", i, j),
+                        resource_id: analysis.resource_id.clone(),
+                        problem,
+                    });
+                }
+            }
+        }
+        unresolved
+    }
+
+    /// Drops the knowledge graph edges that cite a resource that does not resolve, returning their ids. Bindings to the
+    /// dropped edges are left for the caller to deal with, e.g. with `Message::filter_results`.
+    pub fn remove_unresolved_edges(&self, message: &mut Message) -> Vec<String> {
+        let Some(knowledge_graph) = message.knowledge_graph.as_mut() else {
+            return vec![];
+        };
+        let mut removed: Vec<String> = knowledge_graph
+            .edges
+            .iter()
+            .filter(|(_, edge)| !self.edge_problems(edge).is_empty())
+            .map(|(id, _)| id.clone())
+            .collect();
+        removed.sort();
+        removed.iter().for_each(|id| {
+            knowledge_graph.edges.remove(id);
+        });
+        removed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::infores::{AgentType, InforesProblem, InforesRegistry, InforesStatus, KnowledgeLevel};
+    use crate::{Message, CURIE};
+
+    const REGISTRY: &str = r#"
+information_resources:
+  - id: infores:ctd
+    name: Comparative Toxicogenomics Database
+    status: released
+    knowledge_level: knowledge_assertion
+    agent_type: manual_agent
+    xref:
+      - http://ctdbase.org/
+  - id: infores:automat-ctd
+    name: Automat CTD
+    status: released
+    consumes: [infores:ctd]
+  - id: infores:old-kp
+    name: Retired KP
+    status: deprecated
+"#;
+
+    fn curie(s: &str) -> CURIE {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn lookup() {
+        let registry = InforesRegistry::from_yaml(REGISTRY.as_bytes()).unwrap();
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.name(&curie("infores:ctd")), Some("Comparative Toxicogenomics Database"));
+        assert_eq!(registry.knowledge_level(&curie("infores:ctd")), Some(&KnowledgeLevel::KnowledgeAssertion));
+        assert_eq!(registry.agent_type(&curie("infores:ctd")), Some(&AgentType::ManualAgent));
+        assert_eq!(registry.resolve(&curie("infores:old-kp")), Err(InforesProblem::Deprecated));
+        assert_eq!(registry.resolve(&curie("infores:nope")), Err(InforesProblem::Unknown));
+
+        let json = r#"[{ "id": "infores:ctd", "name": "CTD" }]"#;
+        assert_eq!(InforesRegistry::from_json(json.as_bytes()).unwrap().name(&curie("infores:ctd")), Some("CTD"));
+    }
+
+    #[test]
+    fn unexpected_values() {
+        let yaml = r#"
+information_resources:
+  - id: infores:new-kp
+    status: under_review
+    knowledge_level: curated_summary
+    agent_type: llm_agent
+"#;
+        let registry = InforesRegistry::from_yaml(yaml.as_bytes()).unwrap();
+        let resource = registry.get(&curie("infores:new-kp")).unwrap();
+        assert_eq!(resource.status, Some(InforesStatus::Unknown));
+        assert_eq!(resource.knowledge_level, Some(KnowledgeLevel::Unknown));
+        assert_eq!(resource.agent_type, Some(AgentType::Unknown));
+    }
+
+    #[test]
+    fn unresolved_sources() {
+        let registry = InforesRegistry::from_yaml(REGISTRY.as_bytes()).unwrap();
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {},
+                "edges": {
+                    "good": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [
+                            { "resource_id": "infores:ctd", "resource_role": "primary_knowledge_source" },
+                            { "resource_id": "infores:automat-ctd", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:ctd"] }
+                        ]
+                    },
+                    "bad": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148",
+                        "sources": [
+                            { "resource_id": "infores:made-up", "resource_role": "primary_knowledge_source" },
+                            { "resource_id": "infores:automat-ctd", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:old-kp"] }
+                        ]
+                    }
+                }
+            },
+            "results": [{ "node_bindings": {}, "analyses": [{ "resource_id": "infores:old-kp", "edge_bindings": {} }] }]
+        }"#;
+        let mut message: Message = serde_json::from_str(data).unwrap();
+        let paths: Vec<String> = registry.check_message(&message).iter().map(|u| u.to_string()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/knowledge_graph/edges/bad/sources/0/resource_id: unknown information resource (infores:made-up)",
+                "/knowledge_graph/edges/bad/sources/1/upstream_resource_ids/0: deprecated information resource (infores:old-kp)",
+                "/results/0/analyses/0/resource_id: deprecated information resource (infores:old-kp)",
+            ]
+        );
+
+        assert_eq!(registry.remove_unresolved_edges(&mut message), vec!["bad".to_string()]);
+        assert!(registry.check_edge(&message.knowledge_graph.unwrap().edges["good"]).is_empty());
+    }
+}
@@ -1,17 +1,46 @@
 use chrono::SecondsFormat;
 use merge_hashmap::Merge;
-use ordered_float::OrderedFloat;
 use rocket_okapi::okapi::schemars;
 use rocket_okapi::okapi::schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_with::skip_serializing_none;
-use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-pub type BiolinkEntity = String;
-pub type BiolinkPredicate = String;
-pub type CURIE = String;
+#[cfg(feature = "biolink")]
+pub mod biolink;
+pub mod compat;
+pub mod constraints;
+pub mod curie;
+pub mod dedup;
+#[cfg(feature = "infores")]
+pub mod infores;
+pub mod matching;
+pub mod merge;
+pub mod normalization;
+pub mod prefix_map;
+pub mod provenance;
+pub mod pruning;
+pub mod ranking;
+pub mod references;
+pub mod scoring;
+pub mod stream;
+pub mod strict;
+pub mod validation;
+pub mod values;
+
+pub use curie::{BiolinkEntity, BiolinkPredicate, ParseCurieError, CURIE};
+
+/// Members of a TRAPI object that the model has no field for, kept so they can be written back out.
+#[cfg(feature = "extensions")]
+pub type Extensions = BTreeMap<String, Value>;
+
+#[cfg(feature = "extensions")]
+fn merge_extensions(left: &mut Extensions, right: Extensions) {
+    right.into_iter().for_each(|(key, value)| {
+        left.entry(key).or_insert(value);
+    });
+}
 
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub enum LogLevel {
@@ -71,6 +100,11 @@ pub struct NodeBinding {
 
     #[merge(skip)]
     pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 #[skip_serializing_none]
@@ -87,10 +121,14 @@ pub struct Analysis {
     pub edge_bindings: BTreeMap<String, Vec<EdgeBinding>>,
 
     pub attributes: Option<Vec<Attribute>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 impl Analysis {
-    pub fn new(resource_id: String, edge_bindings: BTreeMap<String, Vec<EdgeBinding>>) -> Analysis {
+    pub fn new(resource_id: CURIE, edge_bindings: BTreeMap<String, Vec<EdgeBinding>>) -> Analysis {
         Analysis {
             resource_id,
             score: None,
@@ -98,6 +136,8 @@ impl Analysis {
             support_graphs: None,
             edge_bindings,
             attributes: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
@@ -107,11 +147,20 @@ pub struct EdgeBinding {
     pub id: String,
 
     pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 impl EdgeBinding {
     pub fn new(id: String) -> EdgeBinding {
-        EdgeBinding { id, attributes: vec![] }
+        EdgeBinding {
+            id,
+            attributes: vec![],
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
+        }
     }
 }
 
@@ -122,41 +171,71 @@ pub struct Result {
 
     #[merge(strategy = merge_hashmap::vec::append)]
     pub analyses: Vec<Analysis>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 impl Result {
     pub fn new(node_bindings: BTreeMap<String, Vec<NodeBinding>>, analyses: Vec<Analysis>) -> Result {
-        Result { node_bindings, analyses }
+        Result {
+            node_bindings,
+            analyses,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
+        }
     }
 }
 
 #[skip_serializing_none]
-#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Merge)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Attribute {
-    #[merge(skip)]
     pub attribute_type_id: CURIE,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub original_attribute_name: Option<String>,
 
-    #[merge(skip)]
     pub value: Value,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub value_type_id: Option<CURIE>,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub attribute_source: Option<String>,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub value_url: Option<String>,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub description: Option<String>,
 
-    #[merge(strategy = merge_hashmap::option::overwrite_none)]
-    pub attributes: Option<Vec<Value>>,
-    // pub attributes: Option<Vec<Attribute>>,
+    pub attributes: Option<Vec<Attribute>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
+}
+
+// written out rather than derived so that nested attributes merge recursively
+impl Merge for Attribute {
+    /// Fills in the fields `self` is missing from `other`. Nested attributes with the same type and value are merged in
+    /// turn, and the others are appended.
+    fn merge(&mut self, other: Attribute) {
+        merge_hashmap::option::overwrite_none(&mut self.original_attribute_name, other.original_attribute_name);
+        merge_hashmap::option::overwrite_none(&mut self.value_type_id, other.value_type_id);
+        merge_hashmap::option::overwrite_none(&mut self.attribute_source, other.attribute_source);
+        merge_hashmap::option::overwrite_none(&mut self.value_url, other.value_url);
+        merge_hashmap::option::overwrite_none(&mut self.description, other.description);
+        match (self.attributes.as_mut(), other.attributes) {
+            (Some(left), Some(right)) => right
+                .into_iter()
+                .for_each(|r| match left.iter_mut().find(|l| l.attribute_type_id == r.attribute_type_id && l.value == r.value) {
+                    Some(found_left) => found_left.merge(r),
+                    None => left.push(r),
+                }),
+            (None, right) => self.attributes = right,
+            (Some(_), None) => {}
+        }
+        #[cfg(feature = "extensions")]
+        merge_extensions(&mut self.extensions, other.extensions);
+    }
 }
 
 impl Attribute {
@@ -170,10 +249,27 @@ impl Attribute {
             value_url: None,
             description: None,
             attributes: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+pub enum AttributeConstraintOperator {
+    #[default]
+    #[serde(rename = "==")]
+    Equal,
+    #[serde(rename = ">")]
+    GreaterThan,
+    #[serde(rename = "<")]
+    LessThan,
+    #[serde(rename = "matches")]
+    Matches,
+    #[serde(rename = "===")]
+    StrictEqual,
+}
+
 #[skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct AttributeConstraint {
@@ -181,7 +277,7 @@ pub struct AttributeConstraint {
 
     pub name: String,
 
-    pub operator: String,
+    pub operator: AttributeConstraintOperator,
 
     pub value: Value,
 
@@ -193,7 +289,7 @@ pub struct AttributeConstraint {
 }
 
 impl AttributeConstraint {
-    pub fn new(id: CURIE, name: String, operator: String, value: Value) -> AttributeConstraint {
+    pub fn new(id: CURIE, name: String, operator: AttributeConstraintOperator, value: Value) -> AttributeConstraint {
         AttributeConstraint {
             id,
             name,
@@ -230,24 +326,24 @@ pub enum SetInterpretationEnum {
 #[skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct QNode {
-    #[schemars(regex(pattern = r"^biolink:[a-z][a-z_]*$"))]
     pub ids: Option<Vec<CURIE>>,
 
-    #[schemars(regex(pattern = r"^biolink:[A-Z][a-zA-Z]*$"))]
     pub categories: Option<Vec<BiolinkEntity>>,
 
     pub set_interpretation: Option<SetInterpretationEnum>,
 
-    #[schemars(regex(pattern = r"^biolink:[a-z][a-z_]*$"))]
     pub member_ids: Option<Vec<CURIE>>,
 
     pub constraints: Option<Vec<AttributeConstraint>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 #[skip_serializing_none]
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct QEdge {
-    #[schemars(regex(pattern = r"^biolink:[a-z][a-z_]*$"))]
     pub predicates: Option<Vec<BiolinkPredicate>>,
 
     pub subject: String,
@@ -261,12 +357,20 @@ pub struct QEdge {
     pub qualifier_constraints: Option<Vec<QualifierConstraint>>,
 
     pub provided_by: Option<Value>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
 pub struct QueryGraph {
     pub edges: BTreeMap<String, QEdge>,
     pub nodes: BTreeMap<String, QNode>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 // #[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema, Merge)]
 
@@ -284,6 +388,11 @@ pub struct RetrievalSource {
 
     #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub source_record_urls: Option<Vec<String>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 impl RetrievalSource {
@@ -293,6 +402,8 @@ impl RetrievalSource {
             resource_role,
             upstream_resource_ids: None,
             source_record_urls: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
@@ -303,7 +414,6 @@ pub struct Node {
     pub name: Option<String>,
 
     #[merge(strategy = merge_node_categories)]
-    #[schemars(regex(pattern = r"^biolink:[A-Z][a-zA-Z]*$"))]
     pub categories: BTreeSet<BiolinkEntity>,
 
     #[merge(strategy = merge_attributes)]
@@ -311,6 +421,11 @@ pub struct Node {
 
     #[merge(strategy = merge_hashmap::option::overwrite_none)]
     pub is_set: Option<bool>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 fn merge_node_categories(left: &mut BTreeSet<BiolinkEntity>, right: BTreeSet<BiolinkEntity>) {
@@ -324,7 +439,6 @@ pub struct Edge {
     pub subject: CURIE,
 
     #[merge(skip)]
-    #[schemars(regex(pattern = r"^biolink:[a-z][a-z_]*$"))]
     pub predicate: BiolinkPredicate,
 
     #[merge(skip)]
@@ -338,6 +452,11 @@ pub struct Edge {
 
     #[merge(strategy = merge_edge_qualifiers)]
     pub qualifiers: Option<Vec<Qualifier>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 fn merge_edge_sources(left: &mut Vec<RetrievalSource>, right: Vec<RetrievalSource>) {
@@ -359,6 +478,8 @@ impl Edge {
             sources,
             attributes: None,
             qualifiers: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
@@ -367,13 +488,11 @@ fn merge_edge_attributes(left: &mut Option<Vec<Attribute>>, right: Option<Vec<At
     if let Some(new) = right {
         if let Some(original) = left {
             original.extend(new);
-            original.sort_by(
-                |a, b| match (&a.attribute_type_id, &b.attribute_type_id, &a.original_attribute_name, &b.original_attribute_name) {
-                    (a_ati, b_ati, Some(a_oan), Some(b_oan)) => a_ati.cmp(b_ati).then(a_oan.cmp(b_oan)),
-                    (a_ati, b_ati, None, None) => a_ati.cmp(b_ati),
-                    (_, _, _, _) => Ordering::Less,
-                },
-            );
+            original.sort_by(|a, b| {
+                a.attribute_type_id
+                    .cmp(&b.attribute_type_id)
+                    .then_with(|| a.original_attribute_name.cmp(&b.original_attribute_name))
+            });
             original.dedup();
         } else {
             *left = Some(new);
@@ -400,11 +519,21 @@ pub struct KnowledgeGraph {
 
     #[merge(strategy = merge_hashmap::hashmap::recurse)]
     pub nodes: HashMap<String, Node>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 impl KnowledgeGraph {
     pub fn new(edges: HashMap<String, Edge>, nodes: HashMap<String, Node>) -> KnowledgeGraph {
-        KnowledgeGraph { nodes, edges }
+        KnowledgeGraph {
+            nodes,
+            edges,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
+        }
     }
 }
 
@@ -422,66 +551,18 @@ pub struct Message {
 
     #[merge(strategy = merge_message_auxiliary_graphs)]
     pub auxiliary_graphs: Option<BTreeMap<String, AuxiliaryGraph>>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
-// merging the hard way since Attributes can recurse...which Rust struggles with
 fn merge_message_results(left_results: &mut Option<Vec<Result>>, right_results: Option<Vec<Result>>) {
-    if let Some(right) = right_results.clone() {
-        if let Some(left) = left_results {
-            right.iter().for_each(|right_result| {
-                let right_result_ids: Vec<(String, String)> = right_result
-                    .node_bindings
-                    .iter()
-                    .map(|(k, v)| (k.clone(), v.iter().map(|nb| nb.id.clone()).collect::<Vec<String>>().join(",")))
-                    .collect();
-
-                if let Some(found_left_result) = left.iter_mut().find(|left_result| {
-                    let left_result_ids: Vec<(String, String)> = left_result
-                        .node_bindings
-                        .iter()
-                        .map(|(k, v)| (k.clone(), v.iter().map(|nb| nb.id.clone()).collect::<Vec<String>>().join(",")))
-                        .collect();
-                    left_result_ids == right_result_ids
-                }) {
-                    // deal with Analyses
-                    right_result.analyses.iter().for_each(|right_analysis| {
-                        if let Some(left_analysis) = found_left_result.analyses.iter_mut().find(|found_left_result_analysis| {
-                            if let (Some(left_score), Some(right_score)) = (found_left_result_analysis.score, right_analysis.score) {
-                                found_left_result_analysis.resource_id == right_analysis.resource_id && OrderedFloat(left_score) == OrderedFloat(right_score)
-                            } else {
-                                false
-                            }
-                        }) {
-                            for key in left_analysis.clone().edge_bindings.keys() {
-                                if let (Some(orig_ebs), Some(other_ebs)) = (left_analysis.edge_bindings.get_mut(key), right_analysis.edge_bindings.get(key)) {
-                                    orig_ebs.extend(other_ebs.clone());
-                                }
-                            }
-                        }
-                    });
-
-                    // orig_result.analyses.extend(found_new_result.analyses.clone());
-
-                    // deal with NodeBindings
-                    for key in right_result.node_bindings.keys() {
-                        if let (Some(orig_nbs), Some(new_nb)) = (found_left_result.node_bindings.get_mut(key), right_result.node_bindings.get(key)) {
-                            orig_nbs.iter_mut().for_each(|onb| {
-                                if let Some(fnb) = new_nb.iter().find(|nnb| nnb.id == onb.id) {
-                                    onb.attributes.extend(fnb.attributes.clone());
-                                }
-                                onb.attributes.dedup();
-                            });
-                        }
-                    }
-
-                    // println!("orig_result: {:?}", orig_result);
-                    // println!("new_result: {:?}", found_new_result);
-                } else {
-                    left.push(right_result.clone())
-                }
-            });
-        } else {
-            *left_results = right_results;
+    if let Some(right) = right_results {
+        match left_results {
+            Some(left) => merge::merge_results(left, right, merge::AnalysisIdentity::default()),
+            None => *left_results = Some(right),
         }
     }
 }
@@ -503,6 +584,8 @@ impl Message {
             query_graph: None,
             knowledge_graph: None,
             auxiliary_graphs: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
@@ -514,49 +597,19 @@ pub struct AuxiliaryGraph {
 
     #[merge(strategy = merge_attributes)]
     pub attributes: Vec<Attribute>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    #[merge(strategy = merge_extensions)]
+    pub extensions: Extensions,
 }
 
 fn merge_attributes(left: &mut Vec<Attribute>, right: Vec<Attribute>) {
     right.into_iter().for_each(|r| {
         if let Some(found_left) = left.iter_mut().find(|l| l.attribute_type_id == r.attribute_type_id) {
-            match (&found_left.original_attribute_name, r.original_attribute_name) {
-                (None, Some(new)) => {
-                    found_left.original_attribute_name = Some(new);
-                }
-                (_, _) => {}
-            }
-            match (&found_left.value_type_id, r.value_type_id) {
-                (None, Some(new)) => {
-                    found_left.value_type_id = Some(new);
-                }
-                (_, _) => {}
-            }
-            match (&found_left.attribute_source, r.attribute_source) {
-                (None, Some(new)) => {
-                    found_left.attribute_source = Some(new);
-                }
-                (_, _) => {}
-            }
-            match (&found_left.value_url, r.value_url) {
-                (None, Some(new)) => {
-                    found_left.value_url = Some(new);
-                }
-                (_, _) => {}
-            }
-            match (&found_left.description, r.description) {
-                (None, Some(new)) => {
-                    found_left.description = Some(new);
-                }
-                (_, _) => {}
-            }
-            match (&found_left.attributes, r.attributes) {
-                (None, Some(new)) => {
-                    found_left.attributes = Some(new);
-                }
-                (_, _) => {}
-            }
+            found_left.merge(r);
         } else {
-            left.push(r.clone());
+            left.push(r);
         }
     });
     left.sort_by(|a, b| a.attribute_type_id.cmp(&b.attribute_type_id));
@@ -564,7 +617,12 @@ fn merge_attributes(left: &mut Vec<Attribute>, right: Vec<Attribute>) {
 
 impl AuxiliaryGraph {
     pub fn new(edges: Vec<String>) -> AuxiliaryGraph {
-        AuxiliaryGraph { edges, attributes: vec![] }
+        AuxiliaryGraph {
+            edges,
+            attributes: vec![],
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
+        }
     }
 }
 
@@ -594,6 +652,10 @@ pub struct Response {
     pub schema_version: Option<String>,
 
     pub biolink_version: Option<String>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 impl Response {
@@ -606,6 +668,8 @@ impl Response {
             workflow: None,
             schema_version: None,
             biolink_version: None,
+            #[cfg(feature = "extensions")]
+            extensions: Extensions::new(),
         }
     }
 }
@@ -623,6 +687,10 @@ pub struct Query {
     pub submitter: Option<String>,
 
     pub bypass_cache: Option<bool>,
+
+    #[cfg(feature = "extensions")]
+    #[serde(flatten, serialize_with = "crate::strict::serialize_extensions")]
+    pub extensions: Extensions,
 }
 
 fn example_query() -> Query {
@@ -952,7 +1020,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
     fn invalid_biolink_entity() {
         let data = r#"{ 
             "message": { 
@@ -968,7 +1035,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
     fn invalid_biolink_predicate() {
         let data = r#"{ 
             "message": { 
@@ -1004,6 +1070,145 @@ mod test {
         assert!(true);
     }
 
+    #[test]
+    fn nested_attributes() {
+        let data = r#"{
+            "attribute_type_id": "biolink:has_evidence", "value": "ECO:0000006",
+            "attributes": [
+                { "attribute_type_id": "biolink:publications", "value": ["PMID:1"], "attributes": [{ "attribute_type_id": "biolink:publication_date", "value": "2020" }] },
+                { "attribute_type_id": "biolink:evidence_count", "value": 1 }
+            ]
+        }"#;
+        let mut left: Attribute = serde_json::from_str(data).unwrap();
+        let mut right = left.clone();
+        right.description = Some("experimental evidence".to_string());
+        let sub_attributes = right.attributes.as_mut().unwrap();
+        sub_attributes[0].attributes.as_mut().unwrap()[0].value_type_id = Some("xsd:gYear".parse().unwrap());
+        sub_attributes[1].value = Value::from(2);
+        left.merge(right);
+
+        assert_eq!(left.description.as_deref(), Some("experimental evidence"));
+        let sub_attributes = left.attributes.as_ref().unwrap();
+        assert_eq!(sub_attributes.len(), 3);
+        assert_eq!(sub_attributes[0].attributes.as_ref().unwrap()[0].value_type_id, Some("xsd:gYear".parse().unwrap()));
+        assert_eq!(
+            serde_json::from_str::<Value>(&serde_json::to_string(&left).unwrap()).unwrap()["attributes"][0]["attributes"][0]["value"],
+            "2020"
+        );
+
+        let schema = serde_json::to_value(rocket_okapi::okapi::schemars::schema_for!(Attribute)).unwrap();
+        assert_eq!(schema["properties"]["attributes"]["items"]["$ref"], "#/definitions/Attribute");
+    }
+
+    #[cfg(feature = "extensions")]
+    const EXTENDED_RESPONSE: &str = r#"{
+        "message": {
+            "query_graph": {
+                "nodes": { "n0": { "ids": ["MONDO:0005148"], "x-cache": { "hit": true } }, "n1": { "categories": ["biolink:Gene"] } },
+                "edges": { "e0": { "subject": "n1", "object": "n0", "x-hop-limit": 2 } },
+                "x-template": "gene-disease"
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "MONDO:0005148": { "name": "type 2 diabetes mellitus", "categories": ["biolink:Disease"], "attributes": [], "is_set": false, "x-rank": 1 },
+                    "NCBIGene:5468": {
+                        "name": "PPARG", "categories": ["biolink:Gene"], "is_set": false, "x-aliases": ["PPARG"],
+                        "attributes": [{ "attribute_type_id": "biolink:has_total", "value": 3, "x-unit": "count" }]
+                    }
+                },
+                "edges": {
+                    "e01": {
+                        "subject": "NCBIGene:5468", "predicate": "biolink:gene_associated_with_condition", "object": "MONDO:0005148",
+                        "sources": [{ "resource_id": "infores:kp0", "resource_role": "primary_knowledge_source", "x-release": "2024-05" }],
+                        "x-confidence": 0.75, "x-provenance": { "pipeline": "v2", "steps": [1, 2] }
+                    }
+                },
+                "x-build": 7
+            },
+            "auxiliary_graphs": { "ag1": { "edges": ["e01"], "attributes": [], "x-kind": "support" } },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "MONDO:0005148", "attributes": [], "x-match": "exact" }], "n1": [{ "id": "NCBIGene:5468", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "score": 0.5, "edge_bindings": { "e0": [{ "id": "e01", "attributes": [], "x-weight": 1 }] }, "x-model": "m1" }],
+                "x-cluster": null
+            }],
+            "x-batch": "b1"
+        },
+        "x-trace": "abc"
+    }"#;
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn extensions_round_trip() {
+        let response: Response = serde_json::from_str(EXTENDED_RESPONSE).unwrap();
+        let kg = response.message.knowledge_graph.as_ref().unwrap();
+        assert_eq!(kg.edges["e01"].extensions["x-confidence"], 0.75);
+        assert_eq!(kg.edges["e01"].sources[0].extensions["x-release"], "2024-05");
+        assert_eq!(kg.nodes["NCBIGene:5468"].attributes[0].extensions["x-unit"], "count");
+        assert_eq!(kg.extensions["x-build"], 7);
+        assert_eq!(response.message.query_graph.as_ref().unwrap().extensions["x-template"], "gene-disease");
+        assert_eq!(response.message.auxiliary_graphs.as_ref().unwrap()["ag1"].extensions["x-kind"], "support");
+        let result = &response.message.results.as_ref().unwrap()[0];
+        assert_eq!(result.extensions["x-cluster"], Value::Null);
+        assert_eq!(result.node_bindings["n0"][0].extensions["x-match"], "exact");
+        assert_eq!(result.analyses[0].edge_bindings["e0"][0].extensions["x-weight"], 1);
+        assert_eq!(response.message.extensions["x-batch"], "b1");
+        assert_eq!(response.extensions["x-trace"], "abc");
+
+        let written = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&written).unwrap(), serde_json::from_str::<Value>(EXTENDED_RESPONSE).unwrap());
+        assert_eq!(serde_json::from_str::<Response>(&written).unwrap(), response);
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn extensions_merge() {
+        let mut left: Message = serde_json::from_str::<Response>(EXTENDED_RESPONSE).unwrap().message;
+        let mut right = left.clone();
+        let edge = right.knowledge_graph.as_mut().unwrap().edges.get_mut("e01").unwrap();
+        edge.extensions.insert("x-confidence".to_string(), Value::from(0.1));
+        edge.extensions.insert("x-extra".to_string(), Value::from("kept"));
+        left.merge(right);
+
+        let extensions = &left.knowledge_graph.as_ref().unwrap().edges["e01"].extensions;
+        assert_eq!(extensions["x-confidence"], 0.75);
+        assert_eq!(extensions["x-extra"], "kept");
+
+        // the same holds for merge_with, result merging and edge deduplication
+        let mut right = left.clone();
+        right.extensions.insert("x-batch".to_string(), Value::from("b2"));
+        right.extensions.insert("x-right".to_string(), Value::from(true));
+        right
+            .knowledge_graph
+            .as_mut()
+            .unwrap()
+            .edges
+            .get_mut("e01")
+            .unwrap()
+            .extensions
+            .insert("x-merged".to_string(), Value::from(1));
+        let result = &mut right.results.as_mut().unwrap()[0];
+        result.extensions.insert("x-cluster".to_string(), Value::from(3));
+        result.node_bindings.get_mut("n0").unwrap()[0].extensions.insert("x-right".to_string(), Value::from(true));
+        result.analyses[0].extensions.insert("x-right".to_string(), Value::from(true));
+        left.merge_with(right, &crate::merge::MergePolicy::default()).unwrap();
+        assert_eq!(left.extensions["x-batch"], "b1");
+        assert_eq!(left.extensions["x-right"], true);
+        assert_eq!(left.knowledge_graph.as_ref().unwrap().edges["e01"].extensions["x-merged"], 1);
+        let result = &left.results.as_ref().unwrap()[0];
+        assert_eq!(result.extensions["x-cluster"], Value::Null);
+        assert_eq!(result.node_bindings["n0"][0].extensions["x-right"], true);
+        assert_eq!(result.analyses[0].extensions["x-right"], true);
+
+        let kg = left.knowledge_graph.as_mut().unwrap();
+        let mut duplicate = kg.edges["e01"].clone();
+        duplicate.extensions = [("x-confidence".to_string(), Value::from(0.1)), ("x-duplicate".to_string(), Value::from(true))].into();
+        kg.edges.insert("e02".to_string(), duplicate);
+        left.dedup_edges();
+        let extensions = &left.knowledge_graph.unwrap().edges["e01"].extensions;
+        assert_eq!(extensions["x-confidence"], 0.75);
+        assert_eq!(extensions["x-duplicate"], true);
+    }
+
     #[test]
     fn test_log_entry() {
         let log_entry = LogEntry::new(Some(LogLevel::ERROR), Some("QueryNotTraversable".to_string()), "message".to_string());
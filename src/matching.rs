@@ -0,0 +1,330 @@
+#[cfg(feature = "biolink")]
+use crate::biolink::{BiolinkModel, BiolinkModelError};
+#[cfg(feature = "biolink")]
+use crate::constraints::qualifier_subsumed_by;
+use crate::constraints::{any_of, satisfies_qualifier_constraints, ConstraintError};
+use crate::{Attribute, AttributeConstraint, BiolinkEntity, BiolinkPredicate, Edge, KnowledgeGraph, QEdge, QNode, Qualifier, QualifierConstraint, QueryGraph, CURIE};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeEnd {
+    Subject,
+    Object,
+}
+
+impl fmt::Display for EdgeEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeEnd::Subject => write!(f, "subject"),
+            EdgeEnd::Object => write!(f, "object"),
+        }
+    }
+}
+
+/// One reason why an `Edge` does not satisfy a `QEdge`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    Predicate {
+        expected: Vec<BiolinkPredicate>,
+        found: BiolinkPredicate,
+    },
+    UnknownQueryNode {
+        end: EdgeEnd,
+        qnode_key: String,
+    },
+    MissingNode {
+        end: EdgeEnd,
+        id: CURIE,
+    },
+    NodeId {
+        end: EdgeEnd,
+        expected: Vec<CURIE>,
+        found: CURIE,
+    },
+    Category {
+        end: EdgeEnd,
+        expected: Vec<BiolinkEntity>,
+        found: BTreeSet<BiolinkEntity>,
+    },
+    NodeConstraint {
+        end: EdgeEnd,
+        constraint: AttributeConstraint,
+    },
+    AttributeConstraint {
+        constraint: AttributeConstraint,
+    },
+    QualifierConstraints {
+        expected: Vec<QualifierConstraint>,
+        found: Vec<Qualifier>,
+    },
+    InvalidConstraint {
+        constraint: AttributeConstraint,
+        error: ConstraintError,
+    },
+    /// whether the edge matches depends on a term the Biolink model does not define
+    UnknownTerm {
+        term: String,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Predicate { expected, found } => write!(f, "predicate {} is not one of {:?}", found, expected),
+            Mismatch::UnknownQueryNode { end, qnode_key } => write!(f, "{} query node {} is not in the query graph", end, qnode_key),
+            Mismatch::MissingNode { end, id } => write!(f, "{} {} is not in the knowledge graph", end, id),
+            Mismatch::NodeId { end, expected, found } => write!(f, "{} {} is not one of {:?}", end, found, expected),
+            Mismatch::Category { end, expected, found } => write!(f, "{} categories {:?} do not match {:?}", end, found, expected),
+            Mismatch::NodeConstraint { end, constraint } => write!(f, "{} does not satisfy constraint {}", end, constraint.name),
+            Mismatch::AttributeConstraint { constraint } => write!(f, "edge does not satisfy constraint {}", constraint.name),
+            Mismatch::QualifierConstraints { .. } => write!(f, "edge qualifiers satisfy none of the qualifier sets"),
+            Mismatch::InvalidConstraint { constraint, error } => write!(f, "constraint {} cannot be evaluated: {}", constraint.name, error),
+            Mismatch::UnknownTerm { term } => write!(f, "{} is not defined in the biolink model", term),
+        }
+    }
+}
+
+// constraints that cannot be evaluated are reported as such rather than as unsatisfied
+fn check_constraints(constraints: &[AttributeConstraint], attributes: &[Attribute], unsatisfied: impl Fn(AttributeConstraint) -> Mismatch, mismatches: &mut Vec<Mismatch>) {
+    constraints.iter().for_each(|constraint| match constraint.try_evaluate(attributes) {
+        Ok(true) => {}
+        Ok(false) => mismatches.push(unsatisfied(constraint.clone())),
+        Err(error) => mismatches.push(Mismatch::InvalidConstraint {
+            constraint: constraint.clone(),
+            error,
+        }),
+    });
+}
+
+// each comparison fails with the term it could not judge
+struct Matcher<'a> {
+    predicate: &'a dyn Fn(&BiolinkPredicate, &BiolinkPredicate) -> Result<bool, String>,
+    category: &'a dyn Fn(&BiolinkEntity, &BiolinkEntity) -> Result<bool, String>,
+    qualifier: &'a dyn Fn(&Qualifier, &Qualifier) -> Result<bool, String>,
+}
+
+impl Matcher<'_> {
+    fn end(&self, end: EdgeEnd, id: &CURIE, qnode_key: &str, query_graph: &QueryGraph, knowledge_graph: &KnowledgeGraph, mismatches: &mut Vec<Mismatch>) {
+        let qnode: &QNode = match query_graph.nodes.get(qnode_key) {
+            Some(qnode) => qnode,
+            None => {
+                mismatches.push(Mismatch::UnknownQueryNode {
+                    end,
+                    qnode_key: qnode_key.to_string(),
+                });
+                return;
+            }
+        };
+
+        if let Some(ids) = &qnode.ids {
+            let in_members = qnode.member_ids.as_ref().is_some_and(|members| members.contains(id));
+            if !ids.contains(id) && !in_members {
+                mismatches.push(Mismatch::NodeId {
+                    end,
+                    expected: ids.clone(),
+                    found: id.clone(),
+                });
+            }
+        }
+
+        let node = match knowledge_graph.nodes.get(id.as_str()) {
+            Some(node) => node,
+            None => {
+                mismatches.push(Mismatch::MissingNode { end, id: id.clone() });
+                return;
+            }
+        };
+
+        if let Some(categories) = &qnode.categories {
+            match any_of(categories, |expected| any_of(&node.categories, |found| (self.category)(found, expected))) {
+                Ok(true) => {}
+                Ok(false) => mismatches.push(Mismatch::Category {
+                    end,
+                    expected: categories.clone(),
+                    found: node.categories.clone(),
+                }),
+                Err(term) => mismatches.push(Mismatch::UnknownTerm { term }),
+            }
+        }
+
+        if let Some(constraints) = &qnode.constraints {
+            check_constraints(constraints, &node.attributes, |constraint| Mismatch::NodeConstraint { end, constraint }, mismatches);
+        }
+    }
+
+    fn matches(&self, qedge: &QEdge, edge: &Edge, knowledge_graph: &KnowledgeGraph, query_graph: &QueryGraph) -> Result<(), Vec<Mismatch>> {
+        let mut mismatches = vec![];
+
+        if let Some(predicates) = &qedge.predicates {
+            match any_of(predicates, |expected| (self.predicate)(&edge.predicate, expected)) {
+                Ok(true) => {}
+                Ok(false) => mismatches.push(Mismatch::Predicate {
+                    expected: predicates.clone(),
+                    found: edge.predicate.clone(),
+                }),
+                Err(term) => mismatches.push(Mismatch::UnknownTerm { term }),
+            }
+        }
+
+        self.end(EdgeEnd::Subject, &edge.subject, &qedge.subject, query_graph, knowledge_graph, &mut mismatches);
+        self.end(EdgeEnd::Object, &edge.object, &qedge.object, query_graph, knowledge_graph, &mut mismatches);
+
+        if let Some(constraints) = &qedge.attribute_constraints {
+            let attributes = edge.attributes.as_deref().unwrap_or_default();
+            check_constraints(constraints, attributes, |constraint| Mismatch::AttributeConstraint { constraint }, &mut mismatches);
+        }
+
+        if let Some(constraints) = &qedge.qualifier_constraints {
+            let qualifiers = edge.qualifiers.as_deref().unwrap_or_default();
+            match satisfies_qualifier_constraints(constraints, qualifiers, self.qualifier) {
+                Ok(true) => {}
+                Ok(false) => mismatches.push(Mismatch::QualifierConstraints {
+                    expected: constraints.clone(),
+                    found: qualifiers.to_vec(),
+                }),
+                Err(term) => mismatches.push(Mismatch::UnknownTerm { term }),
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+}
+
+#[cfg(feature = "biolink")]
+fn unknown_term(error: BiolinkModelError) -> String {
+    match error {
+        BiolinkModelError::UnknownTerm(term) => term,
+        error => error.to_string(),
+    }
+}
+
+impl QEdge {
+    /// Checks whether `edge` satisfies this query edge, resolving `subject`/`object` through `query_graph` and the edge
+    /// endpoints through `knowledge_graph`. Predicates and categories must match exactly.
+    pub fn matches(&self, edge: &Edge, knowledge_graph: &KnowledgeGraph, query_graph: &QueryGraph) -> Result<(), Vec<Mismatch>> {
+        let matcher = Matcher {
+            predicate: &|found, expected| Ok(found == expected),
+            category: &|found, expected| Ok(found == expected),
+            qualifier: &|found, expected| Ok(found == expected),
+        };
+        matcher.matches(self, edge, knowledge_graph, query_graph)
+    }
+
+    /// Like `matches`, but also accepts descendant predicates, categories and qualifier values. Terms that are not equal
+    /// and that the model does not define are reported as `Mismatch::UnknownTerm` rather than judged.
+    #[cfg(feature = "biolink")]
+    pub fn matches_with_model(&self, edge: &Edge, knowledge_graph: &KnowledgeGraph, query_graph: &QueryGraph, model: &BiolinkModel) -> Result<(), Vec<Mismatch>> {
+        let matcher = Matcher {
+            predicate: &|found, expected| Ok(found == expected || model.is_subpredicate_of(found, expected).map_err(unknown_term)?),
+            category: &|found, expected| Ok(found == expected || model.is_subclass_of(found, expected).map_err(unknown_term)?),
+            qualifier: &|found, expected| qualifier_subsumed_by(found, expected, model).map_err(unknown_term),
+        };
+        matcher.matches(self, edge, knowledge_graph, query_graph)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matching::{EdgeEnd, Mismatch};
+    use crate::Message;
+
+    fn message() -> Message {
+        let data = r#"{
+            "query_graph": {
+                "nodes": {
+                    "n0": { "categories": ["biolink:ChemicalEntity"] },
+                    "n1": { "ids": ["MONDO:0009061", "MONDO:0004979"] }
+                },
+                "edges": {
+                    "e0": {
+                        "subject": "n0",
+                        "object": "n1",
+                        "predicates": ["biolink:treats"],
+                        "attribute_constraints": [{ "name": "evidence_count gt 20", "id": "biolink:evidence_count", "operator": ">", "value": "20" }],
+                        "qualifier_constraints": [
+                            { "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" }] },
+                            { "qualifier_set": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "abundance" }] }
+                        ]
+                    }
+                }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "CHEBI:6801": { "categories": ["biolink:ChemicalEntity"], "attributes": [] },
+                    "CHEBI:45783": { "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "MONDO:0009061": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "x0": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0009061",
+                        "sources": [{ "resource_id": "infores:kp0", "resource_role": "primary_knowledge_source" }],
+                        "attributes": [{ "attribute_type_id": "biolink:evidence_count", "value": 25 }],
+                        "qualifiers": [{ "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "abundance" }]
+                    },
+                    "x1": {
+                        "subject": "CHEBI:45783", "predicate": "biolink:ameliorates_condition", "object": "MONDO:0005737",
+                        "sources": [{ "resource_id": "infores:kp0", "resource_role": "primary_knowledge_source" }],
+                        "attributes": [{ "attribute_type_id": "biolink:evidence_count", "value": [3, 7] }]
+                    }
+                }
+            }
+        }"#;
+        serde_json::from_str(data).expect("could not parse message")
+    }
+
+    #[test]
+    fn matching_edge() {
+        let message = message();
+        let query_graph = message.query_graph.as_ref().unwrap();
+        let knowledge_graph = message.knowledge_graph.as_ref().unwrap();
+        let qedge = &query_graph.edges["e0"];
+        assert_eq!(qedge.matches(&knowledge_graph.edges["x0"], knowledge_graph, query_graph), Ok(()));
+    }
+
+    #[test]
+    fn mismatching_edge() {
+        let message = message();
+        let query_graph = message.query_graph.as_ref().unwrap();
+        let knowledge_graph = message.knowledge_graph.as_ref().unwrap();
+        let qedge = &query_graph.edges["e0"];
+
+        let mismatches = qedge.matches(&knowledge_graph.edges["x1"], knowledge_graph, query_graph).unwrap_err();
+        assert_eq!(mismatches.len(), 6);
+        assert!(matches!(mismatches[0], Mismatch::Predicate { .. }));
+        assert!(matches!(&mismatches[1], Mismatch::Category { end: EdgeEnd::Subject, .. }));
+        assert!(matches!(&mismatches[2], Mismatch::NodeId { end: EdgeEnd::Object, found, .. } if found == "MONDO:0005737"));
+        assert!(matches!(&mismatches[3], Mismatch::MissingNode { end: EdgeEnd::Object, .. }));
+        assert!(matches!(&mismatches[4], Mismatch::AttributeConstraint { .. }));
+        assert!(matches!(&mismatches[5], Mismatch::QualifierConstraints { .. }));
+    }
+
+    #[cfg(feature = "biolink")]
+    #[test]
+    fn matching_with_model() {
+        use crate::biolink::BiolinkModel;
+
+        let mut message = message();
+        let knowledge_graph = message.knowledge_graph.as_mut().unwrap();
+        let edge = knowledge_graph.edges.get_mut("x0").unwrap();
+        edge.subject = "CHEBI:45783".parse().unwrap();
+        let edge = knowledge_graph.edges.get_mut("x1").unwrap();
+        edge.predicate = "biolink:not_a_predicate".parse().unwrap();
+
+        let query_graph = message.query_graph.as_ref().unwrap();
+        let knowledge_graph = message.knowledge_graph.as_ref().unwrap();
+        let qedge = &query_graph.edges["e0"];
+        let edge = &knowledge_graph.edges["x0"];
+        assert!(qedge.matches(edge, knowledge_graph, query_graph).is_err());
+        assert_eq!(qedge.matches_with_model(edge, knowledge_graph, query_graph, BiolinkModel::bundled_subset()), Ok(()));
+
+        let mismatches = qedge
+            .matches_with_model(&knowledge_graph.edges["x1"], knowledge_graph, query_graph, BiolinkModel::bundled_subset())
+            .unwrap_err();
+        assert!(matches!(&mismatches[0], Mismatch::UnknownTerm { term } if term == "biolink:not_a_predicate"));
+    }
+}
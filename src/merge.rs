@@ -0,0 +1,627 @@
+use crate::validation::escape_pointer_token;
+use crate::{merge_edge_qualifiers, merge_edge_sources, Analysis, Attribute, AuxiliaryGraph, Edge, EdgeBinding, KnowledgeGraph, Message, Node, NodeBinding, QueryGraph, CURIE};
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+/// Identifies a `Result` by the set of node ids bound to each query node, ignoring binding order.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResultKey(BTreeMap<String, BTreeSet<CURIE>>);
+
+impl ResultKey {
+    pub fn node_ids(&self, qnode_key: &str) -> Option<&BTreeSet<CURIE>> {
+        self.0.get(qnode_key)
+    }
+}
+
+impl crate::Result {
+    pub fn key(&self) -> ResultKey {
+        ResultKey(self.node_bindings.iter().map(|(k, v)| (k.clone(), v.iter().map(|nb| nb.id.clone()).collect())).collect())
+    }
+
+    /// Merges `other` into this result: node bindings and analyses are unioned, and analyses that are the same
+    /// according to `identity` are merged into one. Where both have an extension member, this result's is kept.
+    pub fn merge_with(&mut self, other: crate::Result, identity: AnalysisIdentity) {
+        for (qnode_key, bindings) in other.node_bindings {
+            let existing = self.node_bindings.entry(qnode_key).or_default();
+            bindings.into_iter().for_each(|binding| merge_node_binding(existing, binding));
+        }
+
+        for analysis in other.analyses {
+            match self.analyses.iter_mut().find(|a| identity.is_same(a, &analysis)) {
+                Some(existing) => merge_analysis(existing, analysis),
+                None => self.analyses.push(analysis),
+            }
+        }
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut self.extensions, other.extensions);
+    }
+}
+
+/// When two analyses of the same result are considered the same analysis. The default, `Score`, is how
+/// `Message::merge` has always matched analyses, so analyses with different scores are kept apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AnalysisIdentity {
+    /// Same `resource_id`.
+    ResourceId,
+    /// Same `resource_id` and `scoring_method`.
+    ScoringMethod,
+    /// Same `resource_id` and `score`; two unscored analyses count as equal.
+    #[default]
+    Score,
+}
+
+impl AnalysisIdentity {
+    pub fn is_same(&self, left: &Analysis, right: &Analysis) -> bool {
+        left.resource_id == right.resource_id
+            && match self {
+                AnalysisIdentity::ResourceId => true,
+                AnalysisIdentity::ScoringMethod => left.scoring_method == right.scoring_method,
+                AnalysisIdentity::Score => left.score.map(OrderedFloat) == right.score.map(OrderedFloat),
+            }
+    }
+}
+
+/// Merges `right` into `left`. Results with the same `ResultKey` are merged in place; the rest are appended in the
+/// order they appear in `right`, so the outcome only depends on the order of the inputs.
+pub fn merge_results(left: &mut Vec<crate::Result>, right: Vec<crate::Result>, identity: AnalysisIdentity) {
+    let mut index: HashMap<ResultKey, usize> = HashMap::new();
+    left.iter().enumerate().for_each(|(i, result)| {
+        index.entry(result.key()).or_insert(i);
+    });
+
+    for result in right {
+        let key = result.key();
+        match index.get(&key) {
+            Some(&i) => left[i].merge_with(result, identity),
+            None => {
+                index.insert(key, left.len());
+                left.push(result);
+            }
+        }
+    }
+}
+
+fn merge_attribute_list(left: &mut Vec<Attribute>, right: Vec<Attribute>) {
+    right.into_iter().for_each(|attribute| {
+        if !left.contains(&attribute) {
+            left.push(attribute);
+        }
+    });
+}
+
+fn merge_node_binding(left: &mut Vec<NodeBinding>, right: NodeBinding) {
+    match left.iter_mut().find(|nb| nb.id == right.id && nb.query_id == right.query_id) {
+        Some(existing) => {
+            merge_attribute_list(&mut existing.attributes, right.attributes);
+            #[cfg(feature = "extensions")]
+            crate::merge_extensions(&mut existing.extensions, right.extensions);
+        }
+        None => left.push(right),
+    }
+}
+
+pub(crate) fn merge_edge_binding(left: &mut Vec<EdgeBinding>, right: EdgeBinding) {
+    match left.iter_mut().find(|eb| eb.id == right.id) {
+        Some(existing) => {
+            merge_attribute_list(&mut existing.attributes, right.attributes);
+            #[cfg(feature = "extensions")]
+            crate::merge_extensions(&mut existing.extensions, right.extensions);
+        }
+        None => left.push(right),
+    }
+}
+
+pub(crate) fn merge_analysis(left: &mut Analysis, right: Analysis) {
+    for (qedge_key, bindings) in right.edge_bindings {
+        let existing = left.edge_bindings.entry(qedge_key).or_default();
+        bindings.into_iter().for_each(|binding| merge_edge_binding(existing, binding));
+    }
+
+    if let Some(support_graphs) = right.support_graphs {
+        let existing = left.support_graphs.get_or_insert_with(Vec::new);
+        support_graphs.into_iter().for_each(|sg| {
+            if !existing.contains(&sg) {
+                existing.push(sg);
+            }
+        });
+    }
+
+    if let Some(attributes) = right.attributes {
+        merge_attribute_list(left.attributes.get_or_insert_with(Vec::new), attributes);
+    }
+
+    if left.score.is_none() {
+        left.score = right.score;
+    }
+    if left.scoring_method.is_none() {
+        left.scoring_method = right.scoring_method;
+    }
+    #[cfg(feature = "extensions")]
+    crate::merge_extensions(&mut left.extensions, right.extensions);
+}
+
+/// How a field is merged when both messages disagree on its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Keep the value from the message being merged into.
+    #[default]
+    LeftWins,
+    /// Take the value from the message being merged in.
+    RightWins,
+    /// Keep both where the field is a collection; single values behave like `LeftWins`.
+    Union,
+    /// Reject the merge.
+    FailOnConflict,
+}
+
+/// Per-field strategies for `Message::merge_with`. Every disagreement is reported as a `MergeConflict`, whichever
+/// strategy resolves it, except where `Union` keeps both values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergePolicy {
+    pub query_graph: MergeStrategy,
+
+    pub node_name: MergeStrategy,
+
+    pub node_categories: MergeStrategy,
+
+    /// Node, edge and auxiliary graph attributes that share an `attribute_type_id` and `original_attribute_name`
+    /// but differ otherwise.
+    pub attributes: MergeStrategy,
+
+    /// Edges under the same key with a different subject, predicate or object.
+    pub edges: MergeStrategy,
+
+    pub auxiliary_graphs: MergeStrategy,
+
+    pub analysis_identity: AnalysisIdentity,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        MergePolicy {
+            query_graph: MergeStrategy::LeftWins,
+            node_name: MergeStrategy::LeftWins,
+            node_categories: MergeStrategy::Union,
+            attributes: MergeStrategy::Union,
+            edges: MergeStrategy::LeftWins,
+            auxiliary_graphs: MergeStrategy::RightWins,
+            analysis_identity: AnalysisIdentity::default(),
+        }
+    }
+}
+
+/// Two different values for the same field; `path` is a JSON pointer into the merged message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    pub path: String,
+
+    pub left: Value,
+
+    pub right: Value,
+
+    pub strategy: MergeStrategy,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} conflicts with {}", self.path, self.left, self.right)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeError {
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed: Vec<String> = self
+            .conflicts
+            .iter()
+            .filter(|c| c.strategy == MergeStrategy::FailOnConflict)
+            .map(|c| c.to_string())
+            .collect();
+        write!(f, "merge failed on {} conflict(s): {}", failed.len(), failed.join("; "))
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+struct Merger<'a> {
+    policy: &'a MergePolicy,
+    path: Vec<String>,
+    conflicts: Vec<MergeConflict>,
+}
+
+fn sorted<V>(map: HashMap<String, V>) -> Vec<(String, V)> {
+    let mut entries: Vec<(String, V)> = map.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+impl Merger<'_> {
+    fn scoped<F: FnOnce(&mut Self)>(&mut self, token: &str, f: F) {
+        self.path.push(escape_pointer_token(token));
+        f(self);
+        self.path.pop();
+    }
+
+    fn pointer(&self) -> String {
+        self.path.iter().map(|p| format!("/{}", p)).collect()
+    }
+
+    // a single value: no collection to union, so Union keeps the left value
+    fn resolve<T: PartialEq + Serialize>(&mut self, strategy: MergeStrategy, left: &mut T, right: T) {
+        if *left == right {
+            return;
+        }
+        self.conflicts.push(MergeConflict {
+            path: self.pointer(),
+            left: serde_json::to_value(&*left).unwrap_or_default(),
+            right: serde_json::to_value(&right).unwrap_or_default(),
+            strategy,
+        });
+        if strategy == MergeStrategy::RightWins {
+            *left = right;
+        }
+    }
+
+    fn option<T, F: FnOnce(&mut Self, &mut T, T)>(&mut self, left: &mut Option<T>, right: Option<T>, f: F) {
+        match (left.as_mut(), right) {
+            (Some(l), Some(r)) => f(self, l, r),
+            (None, r @ Some(_)) => *left = r,
+            _ => {}
+        }
+    }
+
+    fn message(&mut self, left: &mut Message, right: Message) {
+        self.scoped("query_graph", |m| m.option(&mut left.query_graph, right.query_graph, Merger::query_graph));
+        self.scoped("knowledge_graph", |m| m.option(&mut left.knowledge_graph, right.knowledge_graph, Merger::knowledge_graph));
+        self.scoped("auxiliary_graphs", |m| {
+            m.option(&mut left.auxiliary_graphs, right.auxiliary_graphs, |m, left, right| {
+                for (key, graph) in right {
+                    match left.get_mut(&key) {
+                        Some(existing) => m.scoped(&key, |m| m.auxiliary_graph(existing, graph)),
+                        None => {
+                            left.insert(key, graph);
+                        }
+                    }
+                }
+            })
+        });
+        let identity = self.policy.analysis_identity;
+        self.option(&mut left.results, right.results, |_, left, right| merge_results(left, right, identity));
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut left.extensions, right.extensions);
+    }
+
+    fn query_graph(&mut self, left: &mut QueryGraph, right: QueryGraph) {
+        let strategy = self.policy.query_graph;
+        if strategy != MergeStrategy::Union {
+            self.resolve(strategy, left, right);
+            return;
+        }
+        self.scoped("nodes", |m| {
+            for (key, qnode) in right.nodes {
+                match left.nodes.get_mut(&key) {
+                    Some(existing) => m.scoped(&key, |m| m.resolve(strategy, existing, qnode)),
+                    None => {
+                        left.nodes.insert(key, qnode);
+                    }
+                }
+            }
+        });
+        self.scoped("edges", |m| {
+            for (key, qedge) in right.edges {
+                match left.edges.get_mut(&key) {
+                    Some(existing) => m.scoped(&key, |m| m.resolve(strategy, existing, qedge)),
+                    None => {
+                        left.edges.insert(key, qedge);
+                    }
+                }
+            }
+        });
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut left.extensions, right.extensions);
+    }
+
+    fn knowledge_graph(&mut self, left: &mut KnowledgeGraph, right: KnowledgeGraph) {
+        self.scoped("edges", |m| {
+            for (key, edge) in sorted(right.edges) {
+                match left.edges.get_mut(&key) {
+                    Some(existing) => m.scoped(&key, |m| m.edge(existing, edge)),
+                    None => {
+                        left.edges.insert(key, edge);
+                    }
+                }
+            }
+        });
+        self.scoped("nodes", |m| {
+            for (id, node) in sorted(right.nodes) {
+                match left.nodes.get_mut(&id) {
+                    Some(existing) => m.scoped(&id, |m| m.node(existing, node)),
+                    None => {
+                        left.nodes.insert(id, node);
+                    }
+                }
+            }
+        });
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut left.extensions, right.extensions);
+    }
+
+    fn node(&mut self, left: &mut Node, right: Node) {
+        let policy = self.policy;
+        self.scoped("name", |m| m.option(&mut left.name, right.name, |m, l, r| m.resolve(policy.node_name, l, r)));
+        self.scoped("categories", |m| match policy.node_categories {
+            MergeStrategy::Union => left.categories.extend(right.categories),
+            strategy => m.resolve(strategy, &mut left.categories, right.categories),
+        });
+        self.scoped("attributes", |m| m.attributes(&mut left.attributes, right.attributes));
+        if left.is_set.is_none() {
+            left.is_set = right.is_set;
+        }
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut left.extensions, right.extensions);
+    }
+
+    fn edge(&mut self, left: &mut Edge, right: Edge) {
+        if (&left.subject, &left.predicate, &left.object) != (&right.subject, &right.predicate, &right.object) {
+            self.resolve(self.policy.edges, left, right);
+            return;
+        }
+        merge_edge_sources(&mut left.sources, right.sources);
+        merge_edge_qualifiers(&mut left.qualifiers, right.qualifiers);
+        self.scoped("attributes", |m| m.option(&mut left.attributes, right.attributes, Merger::attributes));
+        #[cfg(feature = "extensions")]
+        crate::merge_extensions(&mut left.extensions, right.extensions);
+    }
+
+    fn auxiliary_graph(&mut self, left: &mut AuxiliaryGraph, right: AuxiliaryGraph) {
+        match self.policy.auxiliary_graphs {
+            MergeStrategy::Union => {
+                right.edges.into_iter().for_each(|edge| {
+                    if !left.edges.contains(&edge) {
+                        left.edges.push(edge);
+                    }
+                });
+                self.scoped("attributes", |m| m.attributes(&mut left.attributes, right.attributes));
+                #[cfg(feature = "extensions")]
+                crate::merge_extensions(&mut left.extensions, right.extensions);
+            }
+            strategy => self.resolve(strategy, left, right),
+        }
+    }
+
+    fn attributes(&mut self, left: &mut Vec<Attribute>, right: Vec<Attribute>) {
+        let strategy = self.policy.attributes;
+        for attribute in right {
+            if left.contains(&attribute) {
+                continue;
+            }
+            let same = left
+                .iter()
+                .position(|a| a.attribute_type_id == attribute.attribute_type_id && a.original_attribute_name == attribute.original_attribute_name);
+            match same {
+                Some(i) if strategy != MergeStrategy::Union => self.scoped(&i.to_string(), |m| m.resolve(strategy, &mut left[i], attribute)),
+                _ => left.push(attribute),
+            }
+        }
+    }
+}
+
+impl Message {
+    /// Merges `other` into this message according to `policy` and returns the conflicts it resolved. If any field
+    /// set to `FailOnConflict` conflicts, this message is left unchanged and all conflicts are returned as an error.
+    pub fn merge_with(&mut self, other: Message, policy: &MergePolicy) -> Result<Vec<MergeConflict>, MergeError> {
+        let mut merged = self.clone();
+        let mut merger = Merger {
+            policy,
+            path: vec![],
+            conflicts: vec![],
+        };
+        merger.message(&mut merged, other);
+
+        if merger.conflicts.iter().any(|c| c.strategy == MergeStrategy::FailOnConflict) {
+            Err(MergeError { conflicts: merger.conflicts })
+        } else {
+            *self = merged;
+            Ok(merger.conflicts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::merge::{merge_results, AnalysisIdentity, MergePolicy, MergeStrategy};
+    use crate::{Message, Result};
+    use merge_hashmap::Merge;
+
+    fn results(data: &str) -> Vec<Result> {
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn order_independent_key() {
+        let mut left = results(
+            r#"[{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }, { "id": "CHEBI:45783", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "score": 0.5, "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }] } }]
+            }]"#,
+        );
+        let right = results(
+            r#"[{
+                "node_bindings": { "n1": [{ "id": "MONDO:0005148", "attributes": [] }], "n0": [{ "id": "CHEBI:45783", "attributes": [] }, { "id": "CHEBI:6801", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "edge_bindings": { "e0": [{ "id": "e02", "attributes": [] }] } }]
+            },
+            {
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                "analyses": []
+            }]"#,
+        );
+        assert_eq!(left[0].key(), right[0].key());
+
+        merge_results(&mut left, right, AnalysisIdentity::ResourceId);
+        assert_eq!(left.len(), 2);
+        assert_eq!(left[0].node_bindings["n0"].len(), 2);
+        assert_eq!(left[0].analyses.len(), 1);
+        let ids: Vec<&str> = left[0].analyses[0].edge_bindings["e0"].iter().map(|eb| eb.id.as_str()).collect();
+        assert_eq!(ids, vec!["e01", "e02"]);
+        assert_eq!(left[0].analyses[0].score, Some(0.5));
+    }
+
+    #[test]
+    fn analysis_identity() {
+        let left = results(
+            r#"[{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:aragorn", "score": 0.5, "scoring_method": "omnicorp", "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }] } }]
+            }]"#,
+        );
+        let right = results(
+            r#"[{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:aragorn", "score": 0.7, "scoring_method": "omnicorp", "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }] } }]
+            }]"#,
+        );
+
+        let merged = |identity: AnalysisIdentity| {
+            let mut merged = left.clone();
+            merge_results(&mut merged, right.clone(), identity);
+            merged[0].analyses.len()
+        };
+        assert_eq!(merged(AnalysisIdentity::ResourceId), 1);
+        assert_eq!(merged(AnalysisIdentity::ScoringMethod), 1);
+        assert_eq!(merged(AnalysisIdentity::Score), 2);
+
+        // Message::merge keeps analyses with different scores apart
+        let mut message = Message {
+            results: Some(left.clone()),
+            ..Message::default()
+        };
+        message.merge(Message {
+            results: Some(right.clone()),
+            ..Message::default()
+        });
+        let scores: Vec<Option<f64>> = message.results.unwrap()[0].analyses.iter().map(|a| a.score).collect();
+        assert_eq!(scores, vec![Some(0.5), Some(0.7)]);
+    }
+
+    #[test]
+    fn edge_binding_union() {
+        let mut left = results(
+            r#"[{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }] }, "support_graphs": ["ag1"] }]
+            }]"#,
+        );
+        let right = results(
+            r#"[{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [{ "attribute_type_id": "biolink:score", "value": 1 }] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }], "e1": [{ "id": "e11", "attributes": [] }] }, "support_graphs": ["ag1", "ag2"] }]
+            }]"#,
+        );
+
+        merge_results(&mut left, right, AnalysisIdentity::default());
+        let analysis = &left[0].analyses[0];
+        assert_eq!(analysis.edge_bindings["e0"].len(), 1);
+        assert_eq!(analysis.edge_bindings["e1"][0].id, "e11");
+        assert_eq!(analysis.support_graphs.as_deref(), Some(&["ag1".to_string(), "ag2".to_string()][..]));
+        assert_eq!(left[0].node_bindings["n0"][0].attributes.len(), 1);
+    }
+
+    fn message(data: &str) -> Message {
+        serde_json::from_str(data).unwrap()
+    }
+
+    const LEFT: &str = r#"{
+        "query_graph": { "nodes": { "n0": { "ids": ["MONDO:0005148"] } }, "edges": {} },
+        "knowledge_graph": {
+            "nodes": {
+                "MONDO:0005148": { "name": "type 2 diabetes mellitus", "categories": ["biolink:Disease"], "attributes": [] }
+            },
+            "edges": {
+                "e01": { "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [] }
+            }
+        },
+        "auxiliary_graphs": { "ag1": { "edges": ["e01"], "attributes": [] } }
+    }"#;
+
+    const RIGHT: &str = r#"{
+        "query_graph": { "nodes": { "n1": { "categories": ["biolink:ChemicalEntity"] } }, "edges": {} },
+        "knowledge_graph": {
+            "nodes": {
+                "MONDO:0005148": { "name": "T2DM", "categories": ["biolink:DiseaseOrPhenotypicFeature"], "attributes": [] }
+            },
+            "edges": {
+                "e01": { "subject": "CHEBI:6801", "predicate": "biolink:affects", "object": "MONDO:0005148", "sources": [] }
+            }
+        },
+        "auxiliary_graphs": { "ag1": { "edges": ["e02"], "attributes": [] } }
+    }"#;
+
+    #[test]
+    fn conflict_report() {
+        let mut left = message(LEFT);
+        let conflicts = left.merge_with(message(RIGHT), &MergePolicy::default()).unwrap();
+        let paths: Vec<&str> = conflicts.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/query_graph",
+                "/knowledge_graph/edges/e01",
+                "/knowledge_graph/nodes/MONDO:0005148/name",
+                "/auxiliary_graphs/ag1"
+            ]
+        );
+        assert_eq!(conflicts[2].left, "type 2 diabetes mellitus");
+        assert_eq!(conflicts[2].right, "T2DM");
+
+        let kg = left.knowledge_graph.as_ref().unwrap();
+        let node = &kg.nodes["MONDO:0005148"];
+        assert_eq!(node.name.as_deref(), Some("type 2 diabetes mellitus"));
+        assert_eq!(node.categories.len(), 2);
+        assert_eq!(kg.edges["e01"].predicate, "biolink:treats");
+        assert_eq!(left.auxiliary_graphs.unwrap()["ag1"].edges, vec!["e02"]);
+    }
+
+    #[test]
+    fn union_and_right_wins() {
+        let policy = MergePolicy {
+            query_graph: MergeStrategy::Union,
+            node_name: MergeStrategy::RightWins,
+            edges: MergeStrategy::RightWins,
+            auxiliary_graphs: MergeStrategy::Union,
+            ..MergePolicy::default()
+        };
+        let mut left = message(LEFT);
+        let conflicts = left.merge_with(message(RIGHT), &policy).unwrap();
+        assert_eq!(conflicts.len(), 2);
+
+        assert_eq!(left.query_graph.as_ref().unwrap().nodes.len(), 2);
+        let kg = left.knowledge_graph.as_ref().unwrap();
+        assert_eq!(kg.nodes["MONDO:0005148"].name.as_deref(), Some("T2DM"));
+        assert_eq!(kg.edges["e01"].predicate, "biolink:affects");
+        assert_eq!(left.auxiliary_graphs.unwrap()["ag1"].edges, vec!["e01", "e02"]);
+    }
+
+    #[test]
+    fn fail_on_conflict() {
+        let policy = MergePolicy {
+            node_name: MergeStrategy::FailOnConflict,
+            ..MergePolicy::default()
+        };
+        let mut left = message(LEFT);
+        let error = left.merge_with(message(RIGHT), &policy).unwrap_err();
+        assert_eq!(error.conflicts.len(), 4);
+        assert!(error.to_string().contains("/knowledge_graph/nodes/MONDO:0005148/name"));
+        assert_eq!(left, message(LEFT));
+
+        // no conflicts, nothing to fail on
+        let mut left = message(LEFT);
+        assert!(left.merge_with(message(LEFT), &policy).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{BiolinkEntity, KnowledgeGraph, Message, NodeBinding, CURIE};
+use merge_hashmap::Merge;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Maps identifiers to their canonical equivalent, e.g. the preferred id returned by node normalization.
+pub trait CurieMapper {
+    /// The canonical id for `curie`, or `None` to leave it unchanged.
+    fn canonical(&self, curie: &CURIE) -> Option<CURIE>;
+}
+
+impl CurieMapper for HashMap<CURIE, CURIE> {
+    fn canonical(&self, curie: &CURIE) -> Option<CURIE> {
+        self.get(curie).cloned()
+    }
+}
+
+impl CurieMapper for BTreeMap<CURIE, CURIE> {
+    fn canonical(&self, curie: &CURIE) -> Option<CURIE> {
+        self.get(curie).cloned()
+    }
+}
+
+impl<F: Fn(&CURIE) -> Option<CURIE>> CurieMapper for F {
+    fn canonical(&self, curie: &CURIE) -> Option<CURIE> {
+        self(curie)
+    }
+}
+
+// remembers every id that actually changed
+struct Remapper<'a> {
+    mapper: &'a dyn CurieMapper,
+    changes: BTreeMap<CURIE, CURIE>,
+}
+
+impl Remapper<'_> {
+    fn map(&mut self, curie: &CURIE) -> CURIE {
+        match self.mapper.canonical(curie) {
+            Some(canonical) if canonical != *curie => {
+                self.changes.insert(curie.clone(), canonical.clone());
+                canonical
+            }
+            _ => curie.clone(),
+        }
+    }
+
+    fn map_all(&mut self, curies: &mut Vec<CURIE>) {
+        let mut mapped: Vec<CURIE> = vec![];
+        for curie in curies.iter() {
+            let curie = self.map(curie);
+            if !mapped.contains(&curie) {
+                mapped.push(curie);
+            }
+        }
+        *curies = mapped;
+    }
+
+    // `qnode_ids` are the (already remapped) ids of the query node the bindings are for
+    fn node_bindings(&mut self, bindings: &mut Vec<NodeBinding>, qnode_ids: Option<&[CURIE]>) {
+        let mut mapped: Vec<NodeBinding> = vec![];
+        for mut binding in bindings.drain(..) {
+            binding.id = self.map(&binding.id);
+            binding.query_id = match qnode_ids {
+                Some(_) => binding.query_id.map(|query_id| self.map(&query_id)).filter(|query_id| *query_id != binding.id),
+                None => None,
+            };
+            match mapped.iter_mut().find(|nb| nb.id == binding.id && nb.query_id == binding.query_id) {
+                Some(existing) => binding.attributes.into_iter().for_each(|a| {
+                    if !existing.attributes.contains(&a) {
+                        existing.attributes.push(a);
+                    }
+                }),
+                None => mapped.push(binding),
+            }
+        }
+        *bindings = mapped;
+    }
+}
+
+impl Message {
+    /// Rewrites every node identifier in the message to its canonical id: knowledge graph node keys, edge subjects and
+    /// objects, node binding ids and query ids, and query node ids and member ids. Knowledge graph nodes that collapse
+    /// onto the same id are merged, in order of their original ids. As the spec asks, a `query_id` is dropped when it
+    /// ends up equal to its binding's id or the bound query node has no `ids`. Returns every id that changed, mapped to
+    /// its replacement.
+    pub fn remap_curies(&mut self, mapper: &dyn CurieMapper) -> BTreeMap<CURIE, CURIE> {
+        let mut remapper = Remapper { mapper, changes: BTreeMap::new() };
+
+        if let Some(query_graph) = self.query_graph.as_mut() {
+            query_graph.nodes.values_mut().for_each(|qnode| {
+                qnode.ids.iter_mut().chain(qnode.member_ids.iter_mut()).for_each(|ids| remapper.map_all(ids));
+            });
+        }
+
+        if let Some(knowledge_graph) = self.knowledge_graph.as_mut() {
+            let mut nodes: Vec<_> = knowledge_graph.nodes.drain().collect();
+            nodes.sort_by(|a, b| a.0.cmp(&b.0));
+            for (id, node) in nodes {
+                let id = match id.parse::<CURIE>() {
+                    Ok(curie) => remapper.map(&curie).to_string(),
+                    Err(_) => id,
+                };
+                match knowledge_graph.nodes.get_mut(&id) {
+                    Some(existing) => existing.merge(node),
+                    None => {
+                        knowledge_graph.nodes.insert(id, node);
+                    }
+                }
+            }
+
+            knowledge_graph.edges.values_mut().for_each(|edge| {
+                edge.subject = remapper.map(&edge.subject);
+                edge.object = remapper.map(&edge.object);
+            });
+        }
+
+        let query_graph = self.query_graph.as_ref();
+        self.results.iter_mut().flatten().for_each(|result| {
+            result.node_bindings.iter_mut().for_each(|(qnode_key, bindings)| {
+                let qnode_ids = query_graph.and_then(|qg| qg.nodes.get(qnode_key)).and_then(|qnode| qnode.ids.as_deref());
+                remapper.node_bindings(bindings, qnode_ids);
+            });
+        });
+
+        remapper.changes
+    }
+}
+
+#[derive(Debug)]
+pub enum NormalizerError {
+    Io(std::io::Error),
+    InvalidRecord { line: usize, message: String },
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for NormalizerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NormalizerError::Io(e) => write!(f, "could not read normalization data: {}", e),
+            NormalizerError::InvalidRecord { line, message } => write!(f, "invalid record on line {}: {}", line, message),
+            NormalizerError::UnsupportedFormat(path) => write!(f, "{} is neither a .tsv nor a .jsonl file", path),
+        }
+    }
+}
+
+impl std::error::Error for NormalizerError {}
+
+impl From<std::io::Error> for NormalizerError {
+    fn from(e: std::io::Error) -> Self {
+        NormalizerError::Io(e)
+    }
+}
+
+/// A set of identifiers for the same concept, with `id` as the preferred one.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct EquivalenceClique {
+    pub id: CURIE,
+
+    #[serde(default)]
+    pub equivalent_identifiers: Vec<CURIE>,
+
+    pub label: Option<String>,
+
+    #[serde(default)]
+    pub types: Vec<BiolinkEntity>,
+}
+
+/// Offline node normalization backed by a dump of equivalence cliques.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LocalNodeNormalizer {
+    cliques: Vec<EquivalenceClique>,
+    index: HashMap<CURIE, usize>,
+}
+
+fn parse_list<T: std::str::FromStr<Err = crate::ParseCurieError>>(field: &str, line: usize) -> Result<Vec<T>, NormalizerError> {
+    field
+        .split('|')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse()
+                .map_err(|e: crate::ParseCurieError| NormalizerError::InvalidRecord { line, message: e.to_string() })
+        })
+        .collect()
+}
+
+impl LocalNodeNormalizer {
+    pub fn new() -> LocalNodeNormalizer {
+        LocalNodeNormalizer::default()
+    }
+
+    /// Reads tab separated `identifier`, `equivalent identifiers`, `preferred label` and `biolink types` columns, with
+    /// lists separated by `|`. Empty lines, lines starting with `#` and a header line starting with `identifier` are skipped.
+    pub fn from_tsv<R: BufRead>(reader: R) -> Result<LocalNodeNormalizer, NormalizerError> {
+        let mut normalizer = LocalNodeNormalizer::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("identifier")) {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').collect();
+            let id = columns[0].trim().parse().map_err(|e: crate::ParseCurieError| NormalizerError::InvalidRecord {
+                line: line_number,
+                message: e.to_string(),
+            })?;
+            normalizer.insert(EquivalenceClique {
+                id,
+                equivalent_identifiers: parse_list(columns.get(1).unwrap_or(&""), line_number)?,
+                label: columns.get(2).map(|l| l.trim()).filter(|l| !l.is_empty()).map(String::from),
+                types: parse_list(columns.get(3).unwrap_or(&""), line_number)?,
+            });
+        }
+        Ok(normalizer)
+    }
+
+    /// Reads one JSON object per line with `id`, `equivalent_identifiers`, `label` and `types` fields.
+    pub fn from_jsonl<R: BufRead>(reader: R) -> Result<LocalNodeNormalizer, NormalizerError> {
+        let mut normalizer = LocalNodeNormalizer::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let clique = serde_json::from_str(&line).map_err(|e| NormalizerError::InvalidRecord {
+                line: i + 1,
+                message: e.to_string(),
+            })?;
+            normalizer.insert(clique);
+        }
+        Ok(normalizer)
+    }
+
+    /// Loads a `.tsv` or `.jsonl` file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<LocalNodeNormalizer, NormalizerError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("tsv") => LocalNodeNormalizer::from_tsv(BufReader::new(File::open(path)?)),
+            Some("jsonl") => LocalNodeNormalizer::from_jsonl(BufReader::new(File::open(path)?)),
+            _ => Err(NormalizerError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    /// Adds a clique; identifiers already known from an earlier clique keep pointing to it.
+    pub fn insert(&mut self, clique: EquivalenceClique) {
+        let position = self.cliques.len();
+        std::iter::once(&clique.id).chain(clique.equivalent_identifiers.iter()).for_each(|curie| {
+            self.index.entry(curie.clone()).or_insert(position);
+        });
+        self.cliques.push(clique);
+    }
+
+    pub fn len(&self) -> usize {
+        self.cliques.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cliques.is_empty()
+    }
+
+    pub fn clique(&self, curie: &CURIE) -> Option<&EquivalenceClique> {
+        self.index.get(curie).map(|&i| &self.cliques[i])
+    }
+
+    /// Fills in missing `Node::name`s from the preferred labels and adds the clique's Biolink types to `Node::categories`.
+    pub fn annotate(&self, knowledge_graph: &mut KnowledgeGraph) {
+        knowledge_graph.nodes.iter_mut().for_each(|(id, node)| {
+            if let Some(clique) = id.parse().ok().and_then(|curie| self.clique(&curie)) {
+                if node.name.is_none() {
+                    node.name = clique.label.clone();
+                }
+                node.categories.extend(clique.types.iter().cloned());
+            }
+        });
+    }
+
+    /// Remaps every id in `message` to its preferred id and annotates the knowledge graph nodes.
+    pub fn normalize(&self, message: &mut Message) -> BTreeMap<CURIE, CURIE> {
+        let changes = message.remap_curies(self);
+        if let Some(knowledge_graph) = message.knowledge_graph.as_mut() {
+            self.annotate(knowledge_graph);
+        }
+        changes
+    }
+}
+
+impl CurieMapper for LocalNodeNormalizer {
+    fn canonical(&self, curie: &CURIE) -> Option<CURIE> {
+        self.clique(curie).map(|clique| clique.id.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::normalization::{CurieMapper, LocalNodeNormalizer, NormalizerError};
+    use crate::{Message, CURIE};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn remap_curies() {
+        let data = r#"{
+            "query_graph": {
+                "nodes": { "n0": { "ids": ["DOID:9352", "MONDO:0005148"] }, "n1": { "categories": ["biolink:ChemicalEntity"] } },
+                "edges": { "e0": { "subject": "n1", "object": "n0", "predicates": ["biolink:treats"] } }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "DOID:9352": { "name": "type 2 diabetes mellitus", "categories": ["biolink:Disease"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:DiseaseOrPhenotypicFeature"], "attributes": [] },
+                    "CHEBI:6801": { "name": "metformin", "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "MONDO:0007455": { "name": "maturity-onset diabetes of the young", "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "e01": { "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "DOID:9352", "sources": [] }
+                }
+            },
+            "results": [{
+                "node_bindings": {
+                    "n0": [
+                        { "id": "DOID:9352", "query_id": "DOID:9352", "attributes": [] },
+                        { "id": "MONDO:0005148", "attributes": [] },
+                        { "id": "MONDO:0007455", "query_id": "DOID:9352", "attributes": [] }
+                    ],
+                    "n1": [{ "id": "CHEBI:6801", "query_id": "CHEBI:6801", "attributes": [] }]
+                },
+                "analyses": []
+            }]
+        }"#;
+        let mut message: Message = serde_json::from_str(data).unwrap();
+
+        let mapping: BTreeMap<CURIE, CURIE> = [("DOID:9352", "MONDO:0005148"), ("CHEBI:6801", "PUBCHEM.COMPOUND:4091")]
+            .iter()
+            .map(|(from, to)| (from.parse().unwrap(), to.parse().unwrap()))
+            .collect();
+        let changes = message.remap_curies(&mapping);
+        assert_eq!(changes, mapping);
+
+        assert_eq!(
+            message.query_graph.as_ref().unwrap().nodes["n0"].ids.as_deref().unwrap(),
+            ["MONDO:0005148".parse::<CURIE>().unwrap()]
+        );
+
+        let kg = message.knowledge_graph.as_ref().unwrap();
+        assert_eq!(kg.nodes.len(), 3);
+        let node = &kg.nodes["MONDO:0005148"];
+        assert_eq!(node.name.as_deref(), Some("type 2 diabetes mellitus"));
+        assert_eq!(node.categories.len(), 2);
+        assert_eq!(kg.edges["e01"].subject, "PUBCHEM.COMPOUND:4091");
+        assert_eq!(kg.edges["e01"].object, "MONDO:0005148");
+
+        let bindings = &message.results.as_ref().unwrap()[0].node_bindings;
+        let n0: Vec<(&str, Option<&str>)> = bindings["n0"].iter().map(|nb| (nb.id.as_str(), nb.query_id.as_ref().map(|q| q.as_str()))).collect();
+        assert_eq!(n0, vec![("MONDO:0005148", None), ("MONDO:0007455", Some("MONDO:0005148"))]);
+        // n1 has no ids, so its bindings cannot have a query_id
+        assert_eq!(bindings["n1"][0].id, "PUBCHEM.COMPOUND:4091");
+        assert_eq!(bindings["n1"][0].query_id, None);
+        assert!(message.check_references().is_empty());
+    }
+
+    #[test]
+    fn closure_mapper() {
+        let mut message: Message =
+            serde_json::from_str(r#"{ "knowledge_graph": { "nodes": { "ncbigene:1017": { "categories": ["biolink:Gene"], "attributes": [] } }, "edges": {} } }"#).unwrap();
+        let changes = message.remap_curies(&|curie: &CURIE| CURIE::new("NCBIGene", curie.local_id()).ok());
+        assert_eq!(changes.len(), 1);
+        assert!(message.knowledge_graph.unwrap().nodes.contains_key("NCBIGene:1017"));
+    }
+
+    const CLIQUES_TSV: &str = "identifier\tequivalent_identifiers\tlabel\ttypes
+MONDO:0005148\tDOID:9352|UMLS:C0011860\ttype 2 diabetes mellitus\tbiolink:Disease|biolink:DiseaseOrPhenotypicFeature
+# metformin
+CHEBI:6801\tPUBCHEM.COMPOUND:4091|DRUGBANK:DB00331\tMetformin\tbiolink:SmallMolecule
+";
+
+    #[test]
+    fn local_normalizer() {
+        let normalizer = LocalNodeNormalizer::from_tsv(CLIQUES_TSV.as_bytes()).unwrap();
+        assert_eq!(normalizer.len(), 2);
+        assert_eq!(normalizer.canonical(&"DOID:9352".parse().unwrap()).unwrap(), "MONDO:0005148");
+        assert_eq!(normalizer.canonical(&"CHEBI:6801".parse().unwrap()).unwrap(), "CHEBI:6801");
+        assert!(normalizer.canonical(&"HP:0001250".parse().unwrap()).is_none());
+
+        let jsonl = r#"{"id": "MONDO:0005148", "equivalent_identifiers": ["DOID:9352", "UMLS:C0011860"], "label": "type 2 diabetes mellitus", "types": ["biolink:Disease"]}
+
+{"id": "CHEBI:6801", "equivalent_identifiers": ["PUBCHEM.COMPOUND:4091"], "types": ["biolink:SmallMolecule"]}"#;
+        let from_jsonl = LocalNodeNormalizer::from_jsonl(jsonl.as_bytes()).unwrap();
+        assert_eq!(from_jsonl.clique(&"PUBCHEM.COMPOUND:4091".parse().unwrap()).unwrap().id, "CHEBI:6801");
+
+        let mut message: Message = serde_json::from_str(
+            r#"{
+                "knowledge_graph": {
+                    "nodes": {
+                        "DOID:9352": { "categories": [], "attributes": [] },
+                        "PUBCHEM.COMPOUND:4091": { "name": "metformin hydrochloride", "categories": ["biolink:ChemicalEntity"], "attributes": [] }
+                    },
+                    "edges": {}
+                }
+            }"#,
+        )
+        .unwrap();
+        let changes = normalizer.normalize(&mut message);
+        assert_eq!(changes.len(), 2);
+
+        let kg = message.knowledge_graph.unwrap();
+        let disease = &kg.nodes["MONDO:0005148"];
+        assert_eq!(disease.name.as_deref(), Some("type 2 diabetes mellitus"));
+        assert_eq!(disease.categories.len(), 2);
+        let drug = &kg.nodes["CHEBI:6801"];
+        assert_eq!(drug.name.as_deref(), Some("metformin hydrochloride"));
+        assert_eq!(drug.categories.len(), 2);
+    }
+
+    #[test]
+    fn invalid_dumps() {
+        let error = LocalNodeNormalizer::from_tsv("MONDO:0005148\tnot a curie\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, NormalizerError::InvalidRecord { line: 1, .. }));
+        let error = LocalNodeNormalizer::from_jsonl("{}\n{\"id\": \"MONDO:0005148\"}".as_bytes()).unwrap_err();
+        assert!(matches!(error, NormalizerError::InvalidRecord { line: 1, .. }));
+        assert!(matches!(LocalNodeNormalizer::from_path("cliques.csv"), Err(NormalizerError::UnsupportedFormat(_))));
+    }
+}
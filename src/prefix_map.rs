@@ -0,0 +1,206 @@
+use crate::CURIE;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Read;
+
+#[derive(Debug)]
+pub enum PrefixMapError {
+    Json(serde_json::Error),
+    InvalidFormat(String),
+}
+
+impl fmt::Display for PrefixMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixMapError::Json(e) => write!(f, "could not parse prefix map: {}", e),
+            PrefixMapError::InvalidFormat(message) => write!(f, "invalid prefix map: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PrefixMapError {}
+
+impl From<serde_json::Error> for PrefixMapError {
+    fn from(e: serde_json::Error) -> Self {
+        PrefixMapError::Json(e)
+    }
+}
+
+/// Bidirectional mapping between CURIE prefixes and IRI namespaces.
+///
+/// Prefixes keep the casing they were loaded with, which for Biolink's `prefix_map` is the canonical one
+/// (e.g. `MONDO`, `NCBIGene`, `CHEBI`). Lookups try the exact prefix first and fall back to a case-insensitive
+/// match only when that match is unambiguous.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PrefixMap {
+    namespaces: BTreeMap<String, String>,
+    lowercase_prefixes: HashMap<String, Vec<String>>,
+    // (namespace, prefix), longest namespace first so contraction picks the most specific match
+    contractions: Vec<(String, String)>,
+}
+
+fn is_absolute_iri(value: &str) -> bool {
+    value.contains("://") || value.starts_with("urn:")
+}
+
+impl PrefixMap {
+    pub fn new() -> PrefixMap {
+        PrefixMap::default()
+    }
+
+    /// Loads either a JSON-LD document with an `@context` object or a flat Biolink `prefix_map` (`{"MONDO": "http://purl.obolibrary.org/obo/MONDO_"}`).
+    pub fn from_reader<R: Read>(reader: R) -> Result<PrefixMap, PrefixMapError> {
+        let value: Value = serde_json::from_reader(reader)?;
+        PrefixMap::from_value(&value)
+    }
+
+    pub fn from_json_str(data: &str) -> Result<PrefixMap, PrefixMapError> {
+        let value: Value = serde_json::from_str(data)?;
+        PrefixMap::from_value(&value)
+    }
+
+    pub fn from_value(value: &Value) -> Result<PrefixMap, PrefixMapError> {
+        let context = match value.get("@context") {
+            Some(Value::Array(contexts)) => contexts.iter().filter_map(|c| c.as_object()).flat_map(|c| c.iter()).collect::<Vec<_>>(),
+            Some(Value::Object(context)) => context.iter().collect(),
+            Some(_) => return Err(PrefixMapError::InvalidFormat("@context must be an object or an array of objects".to_string())),
+            None => match value.as_object() {
+                Some(map) => map.iter().collect(),
+                None => return Err(PrefixMapError::InvalidFormat("expected a JSON object".to_string())),
+            },
+        };
+
+        let mut prefix_map = PrefixMap::new();
+        context.into_iter().filter(|(term, _)| !term.starts_with('@')).for_each(|(term, definition)| {
+            let namespace = match definition {
+                Value::String(iri) => Some(iri.as_str()),
+                Value::Object(expanded) if expanded.get("@prefix") != Some(&Value::Bool(false)) => expanded.get("@id").and_then(|id| id.as_str()),
+                _ => None,
+            };
+            if let Some(namespace) = namespace.filter(|ns| is_absolute_iri(ns)) {
+                prefix_map.insert(term, namespace);
+            }
+        });
+        Ok(prefix_map)
+    }
+
+    pub fn insert(&mut self, prefix: &str, namespace: &str) {
+        if self.namespaces.insert(prefix.to_string(), namespace.to_string()).is_none() {
+            self.lowercase_prefixes.entry(prefix.to_lowercase()).or_default().push(prefix.to_string());
+        }
+        self.contractions.retain(|(_, p)| p != prefix);
+        self.contractions.push((namespace.to_string(), prefix.to_string()));
+        self.contractions.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then(a.1.cmp(&b.1)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.namespaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+    }
+
+    pub fn namespace(&self, prefix: &str) -> Option<&str> {
+        self.canonical_prefix(prefix).and_then(|p| self.namespaces.get(p)).map(|ns| ns.as_str())
+    }
+
+    /// Returns the prefix as it is cased in this map, e.g. `mondo` resolves to `MONDO`.
+    pub fn canonical_prefix(&self, prefix: &str) -> Option<&str> {
+        if let Some((canonical, _)) = self.namespaces.get_key_value(prefix) {
+            return Some(canonical.as_str());
+        }
+        match self.lowercase_prefixes.get(&prefix.to_lowercase()) {
+            Some(candidates) if candidates.len() == 1 => Some(candidates[0].as_str()),
+            _ => None,
+        }
+    }
+
+    /// Rewrites the prefix of `curie` to its canonical casing.
+    pub fn normalize(&self, curie: &CURIE) -> Option<CURIE> {
+        let prefix = self.canonical_prefix(curie.prefix())?;
+        if prefix == curie.prefix() {
+            Some(curie.clone())
+        } else {
+            CURIE::new(prefix, curie.local_id()).ok()
+        }
+    }
+
+    pub fn expand(&self, curie: &CURIE) -> Option<String> {
+        self.namespace(curie.prefix()).map(|ns| format!("{}{}", ns, curie.local_id()))
+    }
+
+    /// Contracts an IRI to a CURIE using the longest matching namespace.
+    pub fn contract(&self, iri: &str) -> Option<CURIE> {
+        self.contractions.iter().find_map(|(namespace, prefix)| {
+            iri.strip_prefix(namespace.as_str())
+                .filter(|local_id| !local_id.is_empty())
+                .and_then(|local_id| CURIE::new(prefix, local_id).ok())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::prefix_map::PrefixMap;
+    use crate::{NodeBinding, CURIE};
+
+    const BIOLINK_PREFIX_MAP: &str = r#"{
+        "MONDO": "http://purl.obolibrary.org/obo/MONDO_",
+        "NCBIGene": "https://identifiers.org/ncbigene:",
+        "CHEBI": "http://purl.obolibrary.org/obo/CHEBI_",
+        "OBO": "http://purl.obolibrary.org/obo/",
+        "biolink": "https://w3id.org/biolink/vocab/"
+    }"#;
+
+    #[test]
+    fn expand_and_contract() {
+        let prefix_map = PrefixMap::from_json_str(BIOLINK_PREFIX_MAP).unwrap();
+        assert_eq!(prefix_map.len(), 5);
+
+        let node_binding: NodeBinding = serde_json::from_str(r#"{ "id": "MONDO:0005737", "attributes": [] }"#).unwrap();
+        assert_eq!(prefix_map.expand(&node_binding.id).as_deref(), Some("http://purl.obolibrary.org/obo/MONDO_0005737"));
+
+        let curie = prefix_map.contract("http://purl.obolibrary.org/obo/MONDO_0005737").unwrap();
+        assert_eq!(curie, "MONDO:0005737");
+        let curie = prefix_map.contract("http://purl.obolibrary.org/obo/UBERON_0000948").unwrap();
+        assert_eq!(curie, "OBO:UBERON_0000948");
+        assert!(prefix_map.contract("http://example.org/thing").is_none());
+    }
+
+    #[test]
+    fn canonical_casing() {
+        let prefix_map = PrefixMap::from_json_str(BIOLINK_PREFIX_MAP).unwrap();
+        assert_eq!(prefix_map.canonical_prefix("ncbigene"), Some("NCBIGene"));
+
+        let curie: CURIE = "ncbigene:1017".parse().unwrap();
+        assert_eq!(prefix_map.expand(&curie).as_deref(), Some("https://identifiers.org/ncbigene:1017"));
+        assert_eq!(prefix_map.normalize(&curie).unwrap(), "NCBIGene:1017");
+
+        let mut prefix_map = prefix_map;
+        prefix_map.insert("ncbigene", "http://example.org/ncbigene/");
+        assert_eq!(prefix_map.canonical_prefix("NCBIGENE"), None);
+        assert_eq!(prefix_map.canonical_prefix("ncbigene"), Some("ncbigene"));
+    }
+
+    #[test]
+    fn json_ld_context() {
+        let data = r#"{
+            "@context": {
+                "@vocab": "https://w3id.org/biolink/vocab/",
+                "id": "@id",
+                "category": { "@id": "biolink:category", "@type": "@id" },
+                "MONDO": { "@id": "http://purl.obolibrary.org/obo/MONDO_", "@prefix": true },
+                "HP": "http://purl.obolibrary.org/obo/HP_",
+                "ex": { "@id": "http://example.org/", "@prefix": false }
+            }
+        }"#;
+
+        let prefix_map = PrefixMap::from_json_str(data).unwrap();
+        assert_eq!(prefix_map.len(), 2);
+        assert_eq!(prefix_map.namespace("MONDO"), Some("http://purl.obolibrary.org/obo/MONDO_"));
+        assert_eq!(prefix_map.contract("http://purl.obolibrary.org/obo/HP_0001250").unwrap(), "HP:0001250");
+        assert!(PrefixMap::from_json_str("[]").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{Edge, Message, ResourceRoleEnum, RetrievalSource, CURIE};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A problem with the retrieval chain recorded in `Edge::sources`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProvenanceError {
+    NoPrimarySource,
+    MultiplePrimarySources(Vec<CURIE>),
+    /// `upstream_id` is listed upstream of `resource_id` but is not one of the edge's sources
+    UnknownUpstream {
+        resource_id: CURIE,
+        upstream_id: CURIE,
+    },
+    /// the sources retrieve from each other in a loop, listed in retrieval order
+    Cycle(Vec<CURIE>),
+}
+
+impl fmt::Display for ProvenanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProvenanceError::NoPrimarySource => write!(f, "no primary knowledge source"),
+            ProvenanceError::MultiplePrimarySources(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                write!(f, "more than one primary knowledge source: {}", ids.join(", "))
+            }
+            ProvenanceError::UnknownUpstream { resource_id, upstream_id } => write!(f, "{} retrieves from {}, which is not a source of the edge", resource_id, upstream_id),
+            ProvenanceError::Cycle(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+                write!(f, "sources retrieve from each other: {}", ids.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProvenanceError {}
+
+fn upstream(source: &RetrievalSource) -> &[CURIE] {
+    source.upstream_resource_ids.as_deref().unwrap_or_default()
+}
+
+impl Edge {
+    fn source(&self, resource_id: &CURIE) -> Option<&RetrievalSource> {
+        self.sources.iter().find(|s| s.resource_id == *resource_id)
+    }
+
+    // the sources that retrieved directly from `resource_id`
+    fn downstream(&self, resource_id: &CURIE) -> impl Iterator<Item = &RetrievalSource> {
+        let resource_id = resource_id.clone();
+        self.sources.iter().filter(move |s| upstream(s).contains(&resource_id))
+    }
+
+    /// Checks that the edge has exactly one primary knowledge source and that the sources form a chain: every
+    /// upstream id is itself a source, and no source ends up retrieving from itself.
+    pub fn check_sources(&self) -> Vec<ProvenanceError> {
+        let mut errors = vec![];
+        let primary: Vec<CURIE> = self
+            .sources
+            .iter()
+            .filter(|s| s.resource_role == ResourceRoleEnum::PrimaryKnowledgeSource)
+            .map(|s| s.resource_id.clone())
+            .collect();
+        match primary.len() {
+            0 => errors.push(ProvenanceError::NoPrimarySource),
+            1 => {}
+            _ => errors.push(ProvenanceError::MultiplePrimarySources(primary)),
+        }
+
+        for source in &self.sources {
+            for upstream_id in upstream(source) {
+                if self.source(upstream_id).is_none() {
+                    errors.push(ProvenanceError::UnknownUpstream {
+                        resource_id: source.resource_id.clone(),
+                        upstream_id: upstream_id.clone(),
+                    });
+                }
+            }
+        }
+
+        // a cycle is found by walking upstream from each source until a source repeats
+        let mut reported: BTreeSet<BTreeSet<CURIE>> = BTreeSet::new();
+        for source in &self.sources {
+            let mut stack: Vec<(Vec<CURIE>, &RetrievalSource)> = vec![(vec![source.resource_id.clone()], source)];
+            while let Some((chain, current)) = stack.pop() {
+                for upstream_id in upstream(current) {
+                    if let Some(position) = chain.iter().position(|id| id == upstream_id) {
+                        let mut cycle: Vec<CURIE> = chain[position..].iter().rev().cloned().collect();
+                        cycle.push(cycle[0].clone());
+                        if reported.insert(chain[position..].iter().cloned().collect()) {
+                            errors.push(ProvenanceError::Cycle(cycle));
+                        }
+                    } else if let Some(next) = self.source(upstream_id) {
+                        let mut chain = chain.clone();
+                        chain.push(upstream_id.clone());
+                        stack.push((chain, next));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Every retrieval path from the primary knowledge source to a source nothing retrieved from in turn, primary source
+    /// first. Supporting data sources are upstream of the primary source and so are not part of any path.
+    pub fn provenance_paths(&self) -> Result<Vec<Vec<&RetrievalSource>>, Vec<ProvenanceError>> {
+        let errors = self.check_sources();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let primary = self
+            .sources
+            .iter()
+            .find(|s| s.resource_role == ResourceRoleEnum::PrimaryKnowledgeSource)
+            .expect("checked sources have a primary knowledge source");
+        let mut paths = vec![];
+        let mut stack: Vec<Vec<&RetrievalSource>> = vec![vec![primary]];
+        while let Some(path) = stack.pop() {
+            let last = path[path.len() - 1];
+            let next: Vec<&RetrievalSource> = self.downstream(&last.resource_id).collect();
+            if next.is_empty() {
+                paths.push(path);
+                continue;
+            }
+            // pushed in reverse so paths come out in the order the sources are listed
+            for source in next.into_iter().rev() {
+                let mut path = path.clone();
+                path.push(source);
+                stack.push(path);
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Records that `resource_id` passed the edge on, as an aggregator retrieving from `upstream_resource_ids`. Does
+    /// nothing if `resource_id` is already an aggregator of the edge.
+    pub fn add_aggregator_source(&mut self, resource_id: CURIE, upstream_resource_ids: Vec<CURIE>) {
+        if self
+            .sources
+            .iter()
+            .any(|s| s.resource_id == resource_id && s.resource_role == ResourceRoleEnum::AggregatorKnowledgeSource)
+        {
+            return;
+        }
+        let mut source = RetrievalSource::new(resource_id, ResourceRoleEnum::AggregatorKnowledgeSource);
+        if !upstream_resource_ids.is_empty() {
+            source.upstream_resource_ids = Some(upstream_resource_ids);
+        }
+        self.sources.push(source);
+    }
+}
+
+impl Message {
+    /// Adds `resource_id` as an aggregator to every knowledge graph edge, as an ARA does before returning edges it got
+    /// from elsewhere. Each edge lists as upstream those of `upstream_resource_ids` that are among its sources, such as
+    /// the KPs the ARA queried.
+    pub fn add_aggregator_source(&mut self, resource_id: &CURIE, upstream_resource_ids: &[CURIE]) {
+        if let Some(knowledge_graph) = self.knowledge_graph.as_mut() {
+            knowledge_graph.edges.values_mut().for_each(|edge| {
+                let upstream: Vec<CURIE> = upstream_resource_ids.iter().filter(|id| edge.source(id).is_some()).cloned().collect();
+                edge.add_aggregator_source(resource_id.clone(), upstream);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::provenance::ProvenanceError;
+    use crate::{Edge, KnowledgeGraph, Message, ResourceRoleEnum, CURIE};
+
+    fn edge(sources: &str) -> Edge {
+        let data = format!(
+            r#"{{ "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": {} }}"#,
+            sources
+        );
+        serde_json::from_str(&data).unwrap()
+    }
+
+    fn ids(path: &[&crate::RetrievalSource]) -> Vec<String> {
+        path.iter().map(|s| s.resource_id.to_string()).collect()
+    }
+
+    #[test]
+    fn provenance_paths() {
+        let mut edge = edge(
+            r#"[
+                { "resource_id": "infores:ctd", "resource_role": "primary_knowledge_source", "upstream_resource_ids": ["infores:pubmed"] },
+                { "resource_id": "infores:pubmed", "resource_role": "supporting_data_source" },
+                { "resource_id": "infores:rtx-kg2", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:ctd"] },
+                { "resource_id": "infores:automat-ctd", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:ctd"] },
+                { "resource_id": "infores:arax", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:rtx-kg2"] }
+            ]"#,
+        );
+        assert!(edge.check_sources().is_empty());
+        let paths: Vec<Vec<String>> = edge.provenance_paths().unwrap().iter().map(|p| ids(p)).collect();
+        assert_eq!(
+            paths,
+            vec![vec!["infores:ctd", "infores:rtx-kg2", "infores:arax"], vec!["infores:ctd", "infores:automat-ctd"]]
+        );
+
+        let aragorn: CURIE = "infores:aragorn".parse().unwrap();
+        let upstream: Vec<CURIE> = vec!["infores:automat-ctd".parse().unwrap(), "infores:arax".parse().unwrap()];
+        edge.add_aggregator_source(aragorn.clone(), upstream.clone());
+        edge.add_aggregator_source(aragorn.clone(), vec![]);
+        assert_eq!(edge.sources.len(), 6);
+        let added = edge.sources.last().unwrap();
+        assert_eq!(added.resource_role, ResourceRoleEnum::AggregatorKnowledgeSource);
+        assert_eq!(added.upstream_resource_ids, Some(upstream));
+        assert!(edge.provenance_paths().unwrap().iter().all(|path| path.last().unwrap().resource_id == aragorn));
+    }
+
+    #[test]
+    fn message_aggregator_source() {
+        let mut message = Message::new();
+        let mut knowledge_graph = KnowledgeGraph::default();
+        knowledge_graph.edges.insert(
+            "e01".to_string(),
+            edge(
+                r#"[
+                    { "resource_id": "infores:ctd", "resource_role": "primary_knowledge_source", "upstream_resource_ids": ["infores:pubmed"] },
+                    { "resource_id": "infores:pubmed", "resource_role": "supporting_data_source" },
+                    { "resource_id": "infores:automat-ctd", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:ctd"] }
+                ]"#,
+            ),
+        );
+        message.knowledge_graph = Some(knowledge_graph);
+
+        // ids that are not sources of the edge are left out of its upstream ids
+        let kps: Vec<CURIE> = vec!["infores:automat-ctd".parse().unwrap(), "infores:rtx-kg2".parse().unwrap()];
+        message.add_aggregator_source(&"infores:aragorn".parse().unwrap(), &kps);
+        let edge = &message.knowledge_graph.unwrap().edges["e01"];
+        assert_eq!(edge.sources.last().unwrap().upstream_resource_ids, Some(vec![kps[0].clone()]));
+        assert!(edge.check_sources().is_empty());
+    }
+
+    #[test]
+    fn broken_chains() {
+        let edge = edge(
+            r#"[
+                { "resource_id": "infores:a", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:b"] },
+                { "resource_id": "infores:b", "resource_role": "aggregator_knowledge_source", "upstream_resource_ids": ["infores:a", "infores:missing"] }
+            ]"#,
+        );
+        let errors = edge.check_sources();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0], ProvenanceError::NoPrimarySource);
+        assert!(matches!(&errors[1], ProvenanceError::UnknownUpstream { upstream_id, .. } if *upstream_id == "infores:missing".parse::<CURIE>().unwrap()));
+        assert!(matches!(&errors[2], ProvenanceError::Cycle(ids) if ids.len() == 3));
+        assert!(edge.provenance_paths().is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Attribute, Message};
+use serde_json::Value;
+use std::collections::{BTreeSet, VecDeque};
+
+/// Number of elements `Message::prune` removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    pub nodes: usize,
+
+    pub edges: usize,
+
+    pub auxiliary_graphs: usize,
+}
+
+// the auxiliary graphs an edge points to through its biolink:support_graphs attribute
+fn support_graph_ids(attributes: &[Attribute]) -> Vec<&str> {
+    attributes
+        .iter()
+        .filter(|a| a.attribute_type_id == "biolink:support_graphs")
+        .flat_map(|a| match &a.value {
+            Value::String(id) => vec![id.as_str()],
+            Value::Array(ids) => ids.iter().filter_map(|id| id.as_str()).collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+enum Reference<'a> {
+    Edge(&'a str),
+    AuxiliaryGraph(&'a str),
+}
+
+impl Message {
+    /// Drops the knowledge graph nodes and edges and the auxiliary graphs that no result refers to. Results refer to
+    /// nodes through node bindings, to edges through edge bindings, and to auxiliary graphs through analysis
+    /// `support_graphs`; auxiliary graphs keep their edges, edges keep their subject and object and the auxiliary graphs
+    /// named in their `biolink:support_graphs` attribute, recursively. A message without `results` is left as is.
+    pub fn prune(&mut self) -> PruneSummary {
+        let Some(results) = &self.results else {
+            return PruneSummary::default();
+        };
+
+        let mut nodes: BTreeSet<String> = BTreeSet::new();
+        let mut edges: BTreeSet<String> = BTreeSet::new();
+        let mut auxiliary_graphs: BTreeSet<String> = BTreeSet::new();
+        let mut queue: VecDeque<Reference> = VecDeque::new();
+
+        for result in results {
+            result.node_bindings.values().flatten().for_each(|nb| {
+                nodes.insert(nb.id.to_string());
+            });
+            for analysis in &result.analyses {
+                analysis.edge_bindings.values().flatten().for_each(|eb| queue.push_back(Reference::Edge(&eb.id)));
+                analysis.support_graphs.iter().flatten().for_each(|sg| queue.push_back(Reference::AuxiliaryGraph(sg)));
+            }
+        }
+
+        while let Some(reference) = queue.pop_front() {
+            match reference {
+                Reference::Edge(id) => {
+                    let Some(edge) = self.knowledge_graph.as_ref().and_then(|kg| kg.edges.get(id)) else {
+                        continue;
+                    };
+                    if edges.insert(id.to_string()) {
+                        nodes.insert(edge.subject.to_string());
+                        nodes.insert(edge.object.to_string());
+                        support_graph_ids(edge.attributes.as_deref().unwrap_or_default())
+                            .into_iter()
+                            .for_each(|sg| queue.push_back(Reference::AuxiliaryGraph(sg)));
+                    }
+                }
+                Reference::AuxiliaryGraph(id) => {
+                    let Some(auxiliary_graph) = self.auxiliary_graphs.as_ref().and_then(|ags| ags.get(id)) else {
+                        continue;
+                    };
+                    if auxiliary_graphs.insert(id.to_string()) {
+                        auxiliary_graph.edges.iter().for_each(|edge| queue.push_back(Reference::Edge(edge)));
+                    }
+                }
+            }
+        }
+
+        let mut summary = PruneSummary::default();
+        if let Some(knowledge_graph) = self.knowledge_graph.as_mut() {
+            let (node_count, edge_count) = (knowledge_graph.nodes.len(), knowledge_graph.edges.len());
+            knowledge_graph.nodes.retain(|id, _| nodes.contains(id));
+            knowledge_graph.edges.retain(|id, _| edges.contains(id));
+            summary.nodes = node_count - knowledge_graph.nodes.len();
+            summary.edges = edge_count - knowledge_graph.edges.len();
+        }
+        if let Some(graphs) = self.auxiliary_graphs.as_mut() {
+            let count = graphs.len();
+            graphs.retain(|id, _| auxiliary_graphs.contains(id));
+            summary.auxiliary_graphs = count - graphs.len();
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pruning::PruneSummary;
+    use crate::Message;
+
+    #[test]
+    fn prune() {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {
+                    "CHEBI:6801": { "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"], "attributes": [] },
+                    "NCBIGene:5468": { "categories": ["biolink:Gene"], "attributes": [] },
+                    "NCBIGene:3630": { "categories": ["biolink:Gene"], "attributes": [] },
+                    "HP:0001250": { "categories": ["biolink:PhenotypicFeature"], "attributes": [] }
+                },
+                "edges": {
+                    "inferred": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [],
+                        "attributes": [{ "attribute_type_id": "biolink:support_graphs", "value": ["ag1"] }]
+                    },
+                    "support1": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:affects", "object": "NCBIGene:5468", "sources": [],
+                        "attributes": [{ "attribute_type_id": "biolink:support_graphs", "value": "ag2" }]
+                    },
+                    "support2": { "subject": "NCBIGene:5468", "predicate": "biolink:gene_associated_with_condition", "object": "MONDO:0005148", "sources": [] },
+                    "nested": { "subject": "NCBIGene:3630", "predicate": "biolink:regulates", "object": "NCBIGene:5468", "sources": [] },
+                    "unused": { "subject": "CHEBI:6801", "predicate": "biolink:causes", "object": "HP:0001250", "sources": [] }
+                }
+            },
+            "auxiliary_graphs": {
+                "ag1": { "edges": ["support1", "support2"], "attributes": [] },
+                "ag2": { "edges": ["nested"], "attributes": [] },
+                "ag3": { "edges": ["unused"], "attributes": [] }
+            },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:kp0", "edge_bindings": { "e0": [{ "id": "inferred", "attributes": [] }] } }]
+            }]
+        }"#;
+
+        let mut message: Message = serde_json::from_str(data).unwrap();
+        let summary = message.prune();
+        assert_eq!(
+            summary,
+            PruneSummary {
+                nodes: 1,
+                edges: 1,
+                auxiliary_graphs: 1
+            }
+        );
+
+        let kg = message.knowledge_graph.as_ref().unwrap();
+        assert!(!kg.nodes.contains_key("HP:0001250"));
+        assert!(kg.nodes.contains_key("NCBIGene:3630"));
+        assert!(kg.edges.contains_key("nested"));
+        assert!(!kg.edges.contains_key("unused"));
+        assert!(!message.auxiliary_graphs.as_ref().unwrap().contains_key("ag3"));
+        assert!(message.check_references().is_empty());
+
+        // nothing left to refer to anything
+        message.results = Some(vec![]);
+        message.prune();
+        assert!(message.knowledge_graph.unwrap().edges.is_empty());
+        assert!(message.auxiliary_graphs.unwrap().is_empty());
+    }
+}
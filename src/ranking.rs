@@ -0,0 +1,166 @@
+use crate::pruning::PruneSummary;
+use crate::{Message, CURIE};
+use ordered_float::OrderedFloat;
+
+/// How a `Result` is scored from the scores of its analyses. Unscored analyses are ignored, as are NaN scores, and a
+/// result with no scored analysis has no score.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScoreStrategy {
+    /// The best analysis score.
+    Max,
+    /// The mean of the analysis scores.
+    Mean,
+    /// The best score among the analyses from this resource.
+    Resource(CURIE),
+}
+
+impl ScoreStrategy {
+    pub fn score(&self, result: &crate::Result) -> Option<f64> {
+        let scores = result
+            .analyses
+            .iter()
+            .filter(|a| match self {
+                ScoreStrategy::Resource(resource_id) => a.resource_id == *resource_id,
+                _ => true,
+            })
+            .filter_map(|a| a.score)
+            .filter(|s| !s.is_nan());
+        let score = match self {
+            ScoreStrategy::Max | ScoreStrategy::Resource(_) => scores.map(OrderedFloat).max().map(|s| s.0),
+            ScoreStrategy::Mean => {
+                let scores: Vec<f64> = scores.collect();
+                match scores.len() {
+                    0 => None,
+                    n => Some(scores.iter().sum::<f64>() / n as f64),
+                }
+            }
+        };
+        // infinite scores of both signs average to NaN
+        score.filter(|s| !s.is_nan())
+    }
+}
+
+impl Message {
+    /// Sorts results from best to worst score; unscored results go last and ties keep their current order.
+    pub fn sort_results_by(&mut self, strategy: ScoreStrategy) {
+        if let Some(results) = self.results.as_mut() {
+            results.sort_by_cached_key(|result| std::cmp::Reverse(strategy.score(result).map(OrderedFloat)));
+        }
+    }
+
+    /// Keeps the results for which `predicate` returns true, then prunes what the removed results referred to.
+    pub fn filter_results<F: FnMut(&crate::Result) -> bool>(&mut self, predicate: F) -> PruneSummary {
+        match self.results.as_mut() {
+            Some(results) => {
+                results.retain(predicate);
+                self.prune()
+            }
+            None => PruneSummary::default(),
+        }
+    }
+
+    /// Keeps the first `n` results, then prunes what the removed results referred to.
+    pub fn truncate_results(&mut self, n: usize) -> PruneSummary {
+        match self.results.as_mut() {
+            Some(results) => {
+                results.truncate(n);
+                self.prune()
+            }
+            None => PruneSummary::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ranking::ScoreStrategy;
+    use crate::Message;
+
+    fn message() -> Message {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {
+                    "CHEBI:6801": { "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "CHEBI:45783": { "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "CHEBI:9150": { "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "e_metformin": { "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [] },
+                    "e_imatinib": { "subject": "CHEBI:45783", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [] },
+                    "e_sitagliptin": { "subject": "CHEBI:9150", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [] }
+                }
+            },
+            "results": [
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:45783", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                    "analyses": [
+                        { "resource_id": "infores:aragorn", "score": 0.2, "edge_bindings": { "e0": [{ "id": "e_imatinib", "attributes": [] }] } },
+                        { "resource_id": "infores:bte", "score": 0.9, "edge_bindings": { "e0": [{ "id": "e_imatinib", "attributes": [] }] } }
+                    ]
+                },
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:9150", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                    "analyses": [{ "resource_id": "infores:aragorn", "edge_bindings": { "e0": [{ "id": "e_sitagliptin", "attributes": [] }] } }]
+                },
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                    "analyses": [
+                        { "resource_id": "infores:aragorn", "score": 0.7, "edge_bindings": { "e0": [{ "id": "e_metformin", "attributes": [] }] } },
+                        { "resource_id": "infores:bte", "score": 0.6, "edge_bindings": { "e0": [{ "id": "e_metformin", "attributes": [] }] } }
+                    ]
+                }
+            ]
+        }"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    fn order(message: &Message) -> Vec<String> {
+        message.results.iter().flatten().map(|r| r.node_bindings["n0"][0].id.to_string()).collect()
+    }
+
+    #[test]
+    fn sort_results() {
+        let mut message = message();
+        message.sort_results_by(ScoreStrategy::Max);
+        assert_eq!(order(&message), vec!["CHEBI:45783", "CHEBI:6801", "CHEBI:9150"]);
+
+        message.sort_results_by(ScoreStrategy::Mean);
+        assert_eq!(order(&message), vec!["CHEBI:6801", "CHEBI:45783", "CHEBI:9150"]);
+
+        message.sort_results_by(ScoreStrategy::Resource("infores:aragorn".parse().unwrap()));
+        assert_eq!(order(&message), vec!["CHEBI:6801", "CHEBI:45783", "CHEBI:9150"]);
+        assert_eq!(ScoreStrategy::Resource("infores:bte".parse().unwrap()).score(&message.results.as_ref().unwrap()[2]), None);
+    }
+
+    #[test]
+    fn nan_scores_sort_last() {
+        let mut message = message();
+        let results = message.results.as_mut().unwrap();
+        results[1].analyses[0].score = Some(f64::NAN);
+        results[2].analyses.iter_mut().for_each(|a| a.score = Some(f64::NAN));
+        results[2].analyses[1].score = Some(0.1);
+        assert_eq!(ScoreStrategy::Max.score(&results[1]), None);
+        assert_eq!(ScoreStrategy::Mean.score(&results[2]), Some(0.1));
+
+        message.sort_results_by(ScoreStrategy::Max);
+        assert_eq!(order(&message), vec!["CHEBI:45783", "CHEBI:6801", "CHEBI:9150"]);
+        message.truncate_results(2);
+        assert_eq!(order(&message), vec!["CHEBI:45783", "CHEBI:6801"]);
+    }
+
+    #[test]
+    fn truncate_and_filter() {
+        let mut message = message();
+        message.sort_results_by(ScoreStrategy::Max);
+        let summary = message.truncate_results(2);
+        assert_eq!(summary.nodes, 1);
+        assert_eq!(summary.edges, 1);
+        assert!(!message.knowledge_graph.as_ref().unwrap().edges.contains_key("e_sitagliptin"));
+
+        let summary = message.filter_results(|r| ScoreStrategy::Mean.score(r).is_some_and(|s| s > 0.6));
+        assert_eq!(order(&message), vec!["CHEBI:6801"]);
+        assert_eq!(summary.edges, 1);
+        assert!(message.check_references().is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::validation::escape_pointer_token;
+use crate::Message;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// `QEdge::subject` or `QEdge::object` names no `QueryGraph::nodes` key
+    QueryNode,
+    /// a `Result::node_bindings` key names no `QueryGraph::nodes` key
+    NodeBindingKey,
+    /// `NodeBinding::id` is missing from `KnowledgeGraph::nodes`
+    NodeBinding,
+    /// an `Analysis::edge_bindings` key names no `QueryGraph::edges` key
+    EdgeBindingKey,
+    /// `EdgeBinding::id` is missing from both `KnowledgeGraph::edges` and `Message::auxiliary_graphs`
+    EdgeBinding,
+    /// `Analysis::support_graphs` names no `Message::auxiliary_graphs` key
+    SupportGraph,
+    /// `AuxiliaryGraph::edges` names no `KnowledgeGraph::edges` key
+    AuxiliaryGraphEdge,
+    /// `Edge::subject` or `Edge::object` is missing from `KnowledgeGraph::nodes`
+    KnowledgeGraphNode,
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ReferenceKind::QueryNode => "unknown query graph node",
+            ReferenceKind::NodeBindingKey => "node binding key is not a query graph node",
+            ReferenceKind::NodeBinding => "node binding is not in the knowledge graph",
+            ReferenceKind::EdgeBindingKey => "edge binding key is not a query graph edge",
+            ReferenceKind::EdgeBinding => "edge binding is not in the knowledge graph or auxiliary graphs",
+            ReferenceKind::SupportGraph => "unknown auxiliary graph",
+            ReferenceKind::AuxiliaryGraphEdge => "auxiliary graph edge is not in the knowledge graph",
+            ReferenceKind::KnowledgeGraphNode => "edge endpoint is not in the knowledge graph",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// A reference that does not resolve, located by a JSON pointer (RFC 6901) relative to the `Message`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DanglingReference {
+    pub path: String,
+
+    pub kind: ReferenceKind,
+
+    pub id: String,
+}
+
+impl fmt::Display for DanglingReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({:?})", self.path, self.kind, self.id)
+    }
+}
+
+fn pointer(tokens: &[&str]) -> String {
+    tokens.iter().map(|t| format!("/{}", escape_pointer_token(t))).collect()
+}
+
+impl Message {
+    /// Reports every binding, query edge endpoint and auxiliary graph reference that does not resolve within this `Message`.
+    ///
+    /// Checks against the query graph are skipped when `query_graph` is `None`.
+    pub fn check_references(&self) -> Vec<DanglingReference> {
+        let mut dangling = vec![];
+        let mut report = |path: String, kind: ReferenceKind, id: &str| dangling.push(DanglingReference { path, kind, id: id.to_string() });
+
+        let has_kg_node = |id: &str| self.knowledge_graph.as_ref().is_some_and(|kg| kg.nodes.contains_key(id));
+        let has_kg_edge = |id: &str| self.knowledge_graph.as_ref().is_some_and(|kg| kg.edges.contains_key(id));
+        let has_auxiliary_graph = |id: &str| self.auxiliary_graphs.as_ref().is_some_and(|ags| ags.contains_key(id));
+
+        if let Some(query_graph) = &self.query_graph {
+            query_graph.edges.iter().for_each(|(qedge_key, qedge)| {
+                [("subject", &qedge.subject), ("object", &qedge.object)].into_iter().for_each(|(field, qnode_key)| {
+                    if !query_graph.nodes.contains_key(qnode_key) {
+                        report(pointer(&["query_graph", "edges", qedge_key, field]), ReferenceKind::QueryNode, qnode_key);
+                    }
+                });
+            });
+        }
+
+        if let Some(knowledge_graph) = &self.knowledge_graph {
+            let mut edge_keys: Vec<&String> = knowledge_graph.edges.keys().collect();
+            edge_keys.sort();
+            edge_keys.into_iter().for_each(|edge_key| {
+                let edge = &knowledge_graph.edges[edge_key];
+                [("subject", &edge.subject), ("object", &edge.object)].into_iter().for_each(|(field, id)| {
+                    if !has_kg_node(id.as_str()) {
+                        report(pointer(&["knowledge_graph", "edges", edge_key, field]), ReferenceKind::KnowledgeGraphNode, id.as_str());
+                    }
+                });
+            });
+        }
+
+        if let Some(auxiliary_graphs) = &self.auxiliary_graphs {
+            auxiliary_graphs.iter().for_each(|(auxiliary_graph_key, auxiliary_graph)| {
+                auxiliary_graph.edges.iter().enumerate().for_each(|(idx, edge_id)| {
+                    if !has_kg_edge(edge_id) {
+                        report(
+                            pointer(&["auxiliary_graphs", auxiliary_graph_key, "edges", &idx.to_string()]),
+                            ReferenceKind::AuxiliaryGraphEdge,
+                            edge_id,
+                        );
+                    }
+                });
+            });
+        }
+
+        if let Some(results) = &self.results {
+            results.iter().enumerate().for_each(|(result_idx, result)| {
+                let result_idx = result_idx.to_string();
+                result.node_bindings.iter().for_each(|(qnode_key, node_bindings)| {
+                    if let Some(query_graph) = &self.query_graph {
+                        if !query_graph.nodes.contains_key(qnode_key) {
+                            report(pointer(&["results", &result_idx, "node_bindings", qnode_key]), ReferenceKind::NodeBindingKey, qnode_key);
+                        }
+                    }
+                    node_bindings.iter().enumerate().for_each(|(idx, node_binding)| {
+                        if !has_kg_node(node_binding.id.as_str()) {
+                            report(
+                                pointer(&["results", &result_idx, "node_bindings", qnode_key, &idx.to_string(), "id"]),
+                                ReferenceKind::NodeBinding,
+                                node_binding.id.as_str(),
+                            );
+                        }
+                    });
+                });
+
+                result.analyses.iter().enumerate().for_each(|(analysis_idx, analysis)| {
+                    let analysis_idx = analysis_idx.to_string();
+                    analysis.edge_bindings.iter().for_each(|(qedge_key, edge_bindings)| {
+                        if let Some(query_graph) = &self.query_graph {
+                            if !query_graph.edges.contains_key(qedge_key) {
+                                report(
+                                    pointer(&["results", &result_idx, "analyses", &analysis_idx, "edge_bindings", qedge_key]),
+                                    ReferenceKind::EdgeBindingKey,
+                                    qedge_key,
+                                );
+                            }
+                        }
+                        edge_bindings.iter().enumerate().for_each(|(idx, edge_binding)| {
+                            if !has_kg_edge(&edge_binding.id) && !has_auxiliary_graph(&edge_binding.id) {
+                                report(
+                                    pointer(&["results", &result_idx, "analyses", &analysis_idx, "edge_bindings", qedge_key, &idx.to_string(), "id"]),
+                                    ReferenceKind::EdgeBinding,
+                                    &edge_binding.id,
+                                );
+                            }
+                        });
+                    });
+                    if let Some(support_graphs) = &analysis.support_graphs {
+                        support_graphs.iter().enumerate().for_each(|(idx, support_graph)| {
+                            if !has_auxiliary_graph(support_graph) {
+                                report(
+                                    pointer(&["results", &result_idx, "analyses", &analysis_idx, "support_graphs", &idx.to_string()]),
+                                    ReferenceKind::SupportGraph,
+                                    support_graph,
+                                );
+                            }
+                        });
+                    }
+                });
+            });
+        }
+
+        dangling
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::references::{DanglingReference, ReferenceKind};
+    use crate::Message;
+
+    #[test]
+    fn consistent_message() {
+        let data = r#"{
+            "query_graph": {
+                "nodes": { "n0": { "ids": ["MONDO:0005737"] }, "n1": { "categories": ["biolink:Gene"] } },
+                "edges": { "e01": { "subject": "n0", "object": "n1" } }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "MONDO:0005737": { "categories": ["biolink:Disease"], "attributes": [] },
+                    "HGNC:17770": { "categories": ["biolink:Gene"], "attributes": [] }
+                },
+                "edges": {
+                    "x17770": {
+                        "predicate": "biolink:related_to", "subject": "MONDO:0005737", "object": "HGNC:17770",
+                        "sources": [{ "resource_id": "infores:kp0", "resource_role": "primary_knowledge_source" }]
+                    }
+                }
+            },
+            "auxiliary_graphs": { "a0": { "edges": ["x17770"], "attributes": [] } },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "MONDO:0005737", "attributes": [] }], "n1": [{ "id": "HGNC:17770", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:ara", "support_graphs": ["a0"], "edge_bindings": { "e01": [{ "id": "x17770", "attributes": [] }] } }]
+            }]
+        }"#;
+
+        let message: Message = serde_json::from_str(data).expect("could not parse message");
+        assert!(message.check_references().is_empty());
+    }
+
+    #[test]
+    fn dangling_references() {
+        let data = r#"{
+            "query_graph": {
+                "nodes": { "n0": { "ids": ["MONDO:0005737"] }, "n1": { "categories": ["biolink:Gene"] } },
+                "edges": { "e01": { "subject": "n0", "object": "n2" } }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "MONDO:0005737": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "x17770": {
+                        "predicate": "biolink:related_to", "subject": "MONDO:0005737", "object": "HGNC:17770",
+                        "sources": [{ "resource_id": "infores:kp0", "resource_role": "primary_knowledge_source" }]
+                    }
+                }
+            },
+            "auxiliary_graphs": { "a0": { "edges": ["x13236"], "attributes": [] } },
+            "results": [{
+                "node_bindings": { "n0": [{ "id": "MONDO:0005737", "attributes": [] }], "n1": [{ "id": "HGNC:17770", "attributes": [] }] },
+                "analyses": [{ "resource_id": "infores:ara", "support_graphs": ["a1"], "edge_bindings": { "e02": [{ "id": "x13236", "attributes": [] }] } }]
+            }]
+        }"#;
+
+        let message: Message = serde_json::from_str(data).expect("could not parse message");
+        let dangling = message.check_references();
+        let expected = vec![
+            ("/query_graph/edges/e01/object", ReferenceKind::QueryNode, "n2"),
+            ("/knowledge_graph/edges/x17770/object", ReferenceKind::KnowledgeGraphNode, "HGNC:17770"),
+            ("/auxiliary_graphs/a0/edges/0", ReferenceKind::AuxiliaryGraphEdge, "x13236"),
+            ("/results/0/node_bindings/n1/0/id", ReferenceKind::NodeBinding, "HGNC:17770"),
+            ("/results/0/analyses/0/edge_bindings/e02", ReferenceKind::EdgeBindingKey, "e02"),
+            ("/results/0/analyses/0/edge_bindings/e02/0/id", ReferenceKind::EdgeBinding, "x13236"),
+            ("/results/0/analyses/0/support_graphs/0", ReferenceKind::SupportGraph, "a1"),
+        ]
+        .into_iter()
+        .map(|(path, kind, id)| DanglingReference {
+            path: path.to_string(),
+            kind,
+            id: id.to_string(),
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(dangling, expected);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::merge::merge_analysis;
+use crate::{Analysis, Message, CURIE};
+use ordered_float::OrderedFloat;
+use std::collections::{BTreeMap, HashMap};
+
+/// Maps raw analysis scores onto a common scale, using the scores of every analysis in the message that shares the
+/// same `scoring_method`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalizer {
+    /// Linear rescaling to [0, 1]; when all scores are equal they become 1.
+    MinMax,
+    /// Percentile rank in [0, 1], ties sharing their average rank.
+    Rank,
+    /// `1 / (1 + e^(-steepness * (score - midpoint)))`.
+    Sigmoid { midpoint: f64, steepness: f64 },
+    /// Distance from the mean in standard deviations; 0 when all scores are equal.
+    ZScore,
+}
+
+impl Normalizer {
+    // the batch statistics are computed once, so normalizing n scores is at worst O(n log n)
+    fn apply(&self, scores: &[f64]) -> Vec<f64> {
+        let n = scores.len() as f64;
+        match self {
+            Normalizer::MinMax => {
+                let min = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                scores.iter().map(|score| if max > min { (score - min) / (max - min) } else { 1.0 }).collect()
+            }
+            Normalizer::Rank => {
+                if scores.len() < 2 {
+                    return vec![1.0; scores.len()];
+                }
+                let mut sorted = scores.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                scores
+                    .iter()
+                    .map(|score| {
+                        let below = sorted.partition_point(|s| s < score);
+                        let ties = sorted.partition_point(|s| s <= score) as f64 - below as f64 - 1.0;
+                        (below as f64 + ties / 2.0) / (n - 1.0)
+                    })
+                    .collect()
+            }
+            Normalizer::Sigmoid { midpoint, steepness } => scores.iter().map(|score| 1.0 / (1.0 + (-steepness * (score - midpoint)).exp())).collect(),
+            Normalizer::ZScore => {
+                let mean = scores.iter().sum::<f64>() / n;
+                let deviation = (scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n).sqrt();
+                scores.iter().map(|score| if deviation > 0.0 { (score - mean) / deviation } else { 0.0 }).collect()
+            }
+        }
+    }
+}
+
+/// Normalizers by `scoring_method`, with an optional fallback for methods that are not registered.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NormalizerRegistry {
+    normalizers: BTreeMap<String, Normalizer>,
+    fallback: Option<Normalizer>,
+}
+
+impl NormalizerRegistry {
+    pub fn new() -> NormalizerRegistry {
+        NormalizerRegistry::default()
+    }
+
+    pub fn insert(&mut self, scoring_method: &str, normalizer: Normalizer) {
+        self.normalizers.insert(scoring_method.to_string(), normalizer);
+    }
+
+    /// Used for analyses whose `scoring_method` is missing or not registered.
+    pub fn set_fallback(&mut self, normalizer: Normalizer) {
+        self.fallback = Some(normalizer);
+    }
+
+    pub fn get(&self, scoring_method: Option<&str>) -> Option<&Normalizer> {
+        scoring_method.and_then(|m| self.normalizers.get(m)).or(self.fallback.as_ref())
+    }
+
+    /// Replaces every analysis score in `message` with its normalized value. Analyses without a score, with a NaN
+    /// score, or without a normalizer for their `scoring_method`, are left alone, and NaN scores are not part of the
+    /// statistics the other scores are normalized by.
+    pub fn normalize(&self, message: &mut Message) {
+        let mut scores: HashMap<Option<String>, Vec<f64>> = HashMap::new();
+        message.results.iter().flatten().flat_map(|r| r.analyses.iter()).for_each(|analysis| {
+            if let Some(score) = analysis.score.filter(|s| !s.is_nan()) {
+                scores.entry(analysis.scoring_method.clone()).or_default().push(score);
+            }
+        });
+
+        // normalized in the order the scores were collected, so each analysis takes the next value for its method
+        let mut normalized: HashMap<Option<String>, std::vec::IntoIter<f64>> = scores
+            .into_iter()
+            .filter_map(|(method, scores)| self.get(method.as_deref()).map(|normalizer| (method, normalizer.apply(&scores).into_iter())))
+            .collect();
+
+        message.results.iter_mut().flatten().flat_map(|r| r.analyses.iter_mut()).for_each(|analysis| {
+            if analysis.score.is_some_and(|s| !s.is_nan()) {
+                if let Some(values) = normalized.get_mut(&analysis.scoring_method) {
+                    analysis.score = values.next();
+                }
+            }
+        });
+    }
+}
+
+/// Combines the analyses of a result into one aggregate score. Unscored analyses are ignored.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Combiner {
+    Max,
+    /// Weighted sum of the best score per `resource_id`; resources without a weight get `default_weight`.
+    WeightedSum {
+        weights: BTreeMap<CURIE, f64>,
+        default_weight: f64,
+    },
+    /// `1 - Π(1 - score)` over the best score per `resource_id`, each clamped to [0, 1].
+    NoisyOr,
+    /// `Σ 1 / (k + rank)`, where `rank` is the 1-based position of the result among all results scored by the same
+    /// `resource_id`; 60 is the usual `k`.
+    ReciprocalRankFusion {
+        k: f64,
+    },
+}
+
+impl Combiner {
+    /// The `scoring_method` of the aggregate analyses this combiner produces.
+    pub fn scoring_method(&self) -> &'static str {
+        match self {
+            Combiner::Max => "max",
+            Combiner::WeightedSum { .. } => "weighted_sum",
+            Combiner::NoisyOr => "noisy_or",
+            Combiner::ReciprocalRankFusion { .. } => "reciprocal_rank_fusion",
+        }
+    }
+
+    fn combine(&self, best: &BTreeMap<&CURIE, f64>, ranks: &BTreeMap<&CURIE, usize>) -> f64 {
+        match self {
+            Combiner::Max => best.values().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Combiner::WeightedSum { weights, default_weight } => best.iter().map(|(r, s)| weights.get(*r).unwrap_or(default_weight) * s).sum(),
+            Combiner::NoisyOr => 1.0 - best.values().map(|s| 1.0 - s.clamp(0.0, 1.0)).product::<f64>(),
+            Combiner::ReciprocalRankFusion { k } => ranks.values().map(|rank| 1.0 / (k + *rank as f64)).sum(),
+        }
+    }
+
+    /// Adds an analysis from `resource_id` to every result with at least one scored analysis. The new analysis has the
+    /// combined score, this combiner's `scoring_method`, and the union of the edge bindings and support graphs of the
+    /// analyses it combines. An aggregate this combiner added earlier for the same `resource_id` is replaced.
+    pub fn aggregate(&self, message: &mut Message, resource_id: &CURIE) {
+        let Some(results) = message.results.as_mut() else {
+            return;
+        };
+        let is_aggregate = |a: &Analysis| a.resource_id == *resource_id && a.scoring_method.as_deref() == Some(self.scoring_method());
+        results.iter_mut().for_each(|r| r.analyses.retain(|a| !is_aggregate(a)));
+
+        // the best score of each resource in each result
+        let best: Vec<BTreeMap<&CURIE, f64>> = results
+            .iter()
+            .map(|result| {
+                let mut best: BTreeMap<&CURIE, f64> = BTreeMap::new();
+                result.analyses.iter().for_each(|a| {
+                    if let Some(score) = a.score {
+                        let entry = best.entry(&a.resource_id).or_insert(score);
+                        *entry = entry.max(score);
+                    }
+                });
+                best
+            })
+            .collect();
+
+        let mut ranks: Vec<BTreeMap<&CURIE, usize>> = vec![BTreeMap::new(); results.len()];
+        let mut by_resource: BTreeMap<&CURIE, Vec<(usize, f64)>> = BTreeMap::new();
+        best.iter()
+            .enumerate()
+            .for_each(|(i, scores)| scores.iter().for_each(|(r, s)| by_resource.entry(r).or_default().push((i, *s))));
+        for (resource, mut scored) in by_resource {
+            scored.sort_by_key(|(i, s)| (std::cmp::Reverse(OrderedFloat(*s)), *i));
+            scored.iter().enumerate().for_each(|(rank, (i, _))| {
+                ranks[*i].insert(resource, rank + 1);
+            });
+        }
+
+        let aggregates: Vec<Option<Analysis>> = results
+            .iter()
+            .zip(best.iter().zip(ranks.iter()))
+            .map(|(result, (best, ranks))| {
+                if best.is_empty() {
+                    return None;
+                }
+                let mut aggregate = Analysis::new(resource_id.clone(), BTreeMap::new());
+                result.analyses.iter().filter(|a| a.score.is_some()).for_each(|a| {
+                    let mut contributed = Analysis::new(a.resource_id.clone(), a.edge_bindings.clone());
+                    contributed.support_graphs = a.support_graphs.clone();
+                    merge_analysis(&mut aggregate, contributed);
+                });
+                aggregate.score = Some(self.combine(best, ranks));
+                aggregate.scoring_method = Some(self.scoring_method().to_string());
+                Some(aggregate)
+            })
+            .collect();
+
+        results.iter_mut().zip(aggregates).for_each(|(result, aggregate)| result.analyses.extend(aggregate));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scoring::{Combiner, Normalizer, NormalizerRegistry};
+    use crate::{Message, CURIE};
+    use std::collections::BTreeMap;
+
+    fn sample() -> Message {
+        let data = r#"{
+            "results": [
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:45783", "attributes": [] }] },
+                    "analyses": [
+                        { "resource_id": "infores:aragorn", "score": 10.0, "scoring_method": "omnicorp", "edge_bindings": { "e0": [{ "id": "a1", "attributes": [] }] } },
+                        { "resource_id": "infores:bte", "score": 0.9, "scoring_method": "bte", "edge_bindings": { "e0": [{ "id": "b1", "attributes": [] }] } }
+                    ]
+                },
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }] },
+                    "analyses": [
+                        { "resource_id": "infores:aragorn", "score": 30.0, "scoring_method": "omnicorp", "edge_bindings": { "e0": [{ "id": "a2", "attributes": [] }] } }
+                    ]
+                },
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:9150", "attributes": [] }] },
+                    "analyses": [
+                        { "resource_id": "infores:aragorn", "score": 20.0, "scoring_method": "omnicorp", "edge_bindings": { "e0": [{ "id": "a3", "attributes": [] }] } },
+                        { "resource_id": "infores:bte", "score": 0.5, "scoring_method": "bte", "edge_bindings": { "e0": [{ "id": "b3", "attributes": [] }] } },
+                        { "resource_id": "infores:improving", "edge_bindings": { "e0": [{ "id": "i3", "attributes": [] }] } }
+                    ]
+                }
+            ]
+        }"#;
+        serde_json::from_str(data).unwrap()
+    }
+
+    fn scores(message: &Message, resource_id: &str) -> Vec<Option<f64>> {
+        message
+            .results
+            .iter()
+            .flatten()
+            .map(|r| r.analyses.iter().find(|a| a.resource_id == resource_id).and_then(|a| a.score))
+            .collect()
+    }
+
+    #[test]
+    fn normalizers() {
+        let mut registry = NormalizerRegistry::new();
+        registry.insert("omnicorp", Normalizer::MinMax);
+        let mut message = sample();
+        registry.normalize(&mut message);
+        assert_eq!(scores(&message, "infores:aragorn"), vec![Some(0.0), Some(1.0), Some(0.5)]);
+        assert_eq!(scores(&message, "infores:bte"), vec![Some(0.9), None, Some(0.5)]);
+
+        registry.insert("omnicorp", Normalizer::Rank);
+        registry.set_fallback(Normalizer::Sigmoid { midpoint: 0.5, steepness: 10.0 });
+        let mut message = message_with_tie();
+        registry.normalize(&mut message);
+        assert_eq!(scores(&message, "infores:aragorn"), vec![Some(0.0), Some(0.75), Some(0.75)]);
+        assert_eq!(scores(&message, "infores:bte")[2], Some(0.5));
+
+        let mut registry = NormalizerRegistry::new();
+        registry.insert("omnicorp", Normalizer::ZScore);
+        let mut message = sample();
+        registry.normalize(&mut message);
+        let z = scores(&message, "infores:aragorn");
+        assert_eq!(z[2], Some(0.0));
+        assert!((z[1].unwrap() - 1.224744871391589).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nan_scores() {
+        let mut registry = NormalizerRegistry::new();
+        registry.insert("omnicorp", Normalizer::MinMax);
+        registry.insert("bte", Normalizer::ZScore);
+        let mut message = sample();
+        message.results.as_mut().unwrap()[1].analyses[0].score = Some(f64::NAN);
+        message.results.as_mut().unwrap()[2].analyses[1].score = Some(f64::NAN);
+        registry.normalize(&mut message);
+        let minmax = scores(&message, "infores:aragorn");
+        assert_eq!((minmax[0], minmax[2]), (Some(0.0), Some(1.0)));
+        assert!(minmax[1].unwrap().is_nan());
+        let z = scores(&message, "infores:bte");
+        assert_eq!(z[0], Some(0.0));
+        assert!(z[2].unwrap().is_nan());
+    }
+
+    fn message_with_tie() -> Message {
+        let mut message = sample();
+        message.results.as_mut().unwrap()[2].analyses[0].score = Some(30.0);
+        message
+    }
+
+    #[test]
+    fn combiners() {
+        let ara: CURIE = "infores:ara".parse().unwrap();
+        let mut message = sample();
+
+        Combiner::Max.aggregate(&mut message, &ara);
+        assert_eq!(scores(&message, "infores:ara"), vec![Some(10.0), Some(30.0), Some(20.0)]);
+        let aggregate = message.results.as_ref().unwrap()[0].analyses.last().unwrap();
+        assert_eq!(aggregate.scoring_method.as_deref(), Some("max"));
+        assert_eq!(aggregate.edge_bindings["e0"].len(), 2);
+
+        // re-running replaces the earlier aggregate instead of feeding on it
+        Combiner::Max.aggregate(&mut message, &ara);
+        assert_eq!(message.results.as_ref().unwrap()[0].analyses.len(), 3);
+
+        let mut message = sample();
+        let mut registry = NormalizerRegistry::new();
+        registry.insert("omnicorp", Normalizer::MinMax);
+        registry.normalize(&mut message);
+        Combiner::NoisyOr.aggregate(&mut message, &ara);
+        let noisy_or = scores(&message, "infores:ara");
+        assert!((noisy_or[0].unwrap() - 0.9).abs() < 1e-9);
+        assert!((noisy_or[2].unwrap() - 0.75).abs() < 1e-9);
+
+        let mut message = sample();
+        let weights: BTreeMap<CURIE, f64> = [("infores:bte".parse().unwrap(), 10.0)].into_iter().collect();
+        Combiner::WeightedSum { weights, default_weight: 0.1 }.aggregate(&mut message, &ara);
+        assert_eq!(scores(&message, "infores:ara"), vec![Some(10.0), Some(3.0), Some(7.0)]);
+
+        let mut message = sample();
+        Combiner::ReciprocalRankFusion { k: 60.0 }.aggregate(&mut message, &ara);
+        let rrf = scores(&message, "infores:ara");
+        assert!((rrf[0].unwrap() - (1.0 / 63.0 + 1.0 / 61.0)).abs() < 1e-12);
+        assert!((rrf[1].unwrap() - 1.0 / 61.0).abs() < 1e-12);
+        assert!((rrf[2].unwrap() - (1.0 / 62.0 + 1.0 / 62.0)).abs() < 1e-12);
+    }
+}
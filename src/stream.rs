@@ -0,0 +1,763 @@
+use crate::{AuxiliaryGraph, Edge, LogEntry, Node, QueryGraph, Workflow};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use std::fmt;
+use std::io::{BufReader, Read, Write};
+use std::marker::PhantomData;
+
+/// Receives the parts of a `Message` one at a time while it is being read. Every method defaults to discarding its
+/// argument. Extension members of the message and knowledge graph themselves are skipped.
+pub trait MessageVisitor {
+    fn query_graph(&mut self, _query_graph: QueryGraph) {}
+
+    fn node(&mut self, _id: String, _node: Node) {}
+
+    fn edge(&mut self, _id: String, _edge: Edge) {}
+
+    fn auxiliary_graph(&mut self, _id: String, _auxiliary_graph: AuxiliaryGraph) {}
+
+    fn result(&mut self, _result: crate::Result) {}
+}
+
+/// Everything in a `Response` except its `message`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResponseHeader {
+    pub workflow: Option<Vec<Workflow>>,
+
+    pub status: Option<String>,
+
+    pub description: Option<String>,
+
+    pub logs: Option<Vec<LogEntry>>,
+
+    pub schema_version: Option<String>,
+
+    pub biolink_version: Option<String>,
+
+    #[cfg(feature = "extensions")]
+    pub extensions: crate::Extensions,
+}
+
+/// Reads a `Response` from `reader`, handing the message's graph elements and results to `visitor` as they are parsed
+/// so that only one of them is held in memory at a time.
+pub fn read_response<R: Read, V: MessageVisitor>(reader: R, visitor: &mut V) -> Result<ResponseHeader, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let header = ResponseSeed { visitor: Some(visitor) }.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(header)
+}
+
+/// Reads a bare `Message` (e.g. the `message` of a `Query`) from `reader`.
+pub fn read_message<R: Read, V: MessageVisitor>(reader: R, visitor: &mut V) -> Result<(), serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    Nullable(MessageSeed { visitor }).deserialize(&mut deserializer)?;
+    deserializer.end()
+}
+
+/// Reads only the status, versions and logs of a `Response`, skipping over the message without building any of it.
+pub fn skim_response<R: Read>(reader: R) -> Result<ResponseHeader, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(reader));
+    let header = ResponseSeed::<'_, Discard> { visitor: None }.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(header)
+}
+
+struct Discard;
+
+impl MessageVisitor for Discard {}
+
+// null is accepted wherever the model has an Option
+struct Nullable<S>(S);
+
+impl<'de, S: DeserializeSeed<'de, Value = ()>> DeserializeSeed<'de> for Nullable<S> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de, Value = ()>> Visitor<'de> for Nullable<S> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an optional value")
+    }
+
+    fn visit_none<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        self.0.deserialize(deserializer)
+    }
+}
+
+struct ResponseSeed<'a, V> {
+    visitor: Option<&'a mut V>,
+}
+
+impl<'de, V: MessageVisitor> DeserializeSeed<'de> for ResponseSeed<'_, V> {
+    type Value = ResponseHeader;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<ResponseHeader, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V: MessageVisitor> Visitor<'de> for ResponseSeed<'_, V> {
+    type Value = ResponseHeader;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TRAPI Response")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<ResponseHeader, A::Error> {
+        let mut header = ResponseHeader::default();
+        let mut seen_message = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "message" => {
+                    seen_message = true;
+                    match self.visitor.as_deref_mut() {
+                        Some(visitor) => map.next_value_seed(MessageSeed { visitor })?,
+                        None => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                "workflow" => header.workflow = map.next_value()?,
+                "status" => header.status = map.next_value()?,
+                "description" => header.description = map.next_value()?,
+                "logs" => header.logs = map.next_value()?,
+                "schema_version" => header.schema_version = map.next_value()?,
+                "biolink_version" => header.biolink_version = map.next_value()?,
+                #[cfg(feature = "extensions")]
+                _ => {
+                    header.extensions.insert(key, map.next_value()?);
+                }
+                #[cfg(not(feature = "extensions"))]
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !seen_message {
+            return Err(serde::de::Error::missing_field("message"));
+        }
+        Ok(header)
+    }
+}
+
+struct MessageSeed<'a, V> {
+    visitor: &'a mut V,
+}
+
+impl<'de, V: MessageVisitor> DeserializeSeed<'de> for MessageSeed<'_, V> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V: MessageVisitor> Visitor<'de> for MessageSeed<'_, V> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TRAPI Message")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "query_graph" => {
+                    if let Some(query_graph) = map.next_value::<Option<QueryGraph>>()? {
+                        self.visitor.query_graph(query_graph);
+                    }
+                }
+                "knowledge_graph" => map.next_value_seed(Nullable(KnowledgeGraphSeed { visitor: &mut *self.visitor }))?,
+                "auxiliary_graphs" => map.next_value_seed(Nullable(Entries {
+                    visitor: &mut *self.visitor,
+                    each: |v: &mut V, id, graph| v.auxiliary_graph(id, graph),
+                    marker: PhantomData,
+                }))?,
+                "results" => map.next_value_seed(Nullable(Results { visitor: &mut *self.visitor }))?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+struct KnowledgeGraphSeed<'a, V> {
+    visitor: &'a mut V,
+}
+
+impl<'de, V: MessageVisitor> DeserializeSeed<'de> for KnowledgeGraphSeed<'_, V> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V: MessageVisitor> Visitor<'de> for KnowledgeGraphSeed<'_, V> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a TRAPI KnowledgeGraph")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "nodes" => map.next_value_seed(Entries {
+                    visitor: &mut *self.visitor,
+                    each: |v: &mut V, id, node| v.node(id, node),
+                    marker: PhantomData,
+                })?,
+                "edges" => map.next_value_seed(Entries {
+                    visitor: &mut *self.visitor,
+                    each: |v: &mut V, id, edge| v.edge(id, edge),
+                    marker: PhantomData,
+                })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// a JSON object whose values are handed over one entry at a time
+struct Entries<'a, V, T, F> {
+    visitor: &'a mut V,
+    each: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, V, T: serde::Deserialize<'de>, F: Fn(&mut V, String, T)> DeserializeSeed<'de> for Entries<'_, V, T, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, V, T: serde::Deserialize<'de>, F: Fn(&mut V, String, T)> Visitor<'de> for Entries<'_, V, T, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some((key, value)) = map.next_entry::<String, T>()? {
+            (self.each)(self.visitor, key, value);
+        }
+        Ok(())
+    }
+}
+
+struct Results<'a, V> {
+    visitor: &'a mut V,
+}
+
+impl<'de, V: MessageVisitor> DeserializeSeed<'de> for Results<'_, V> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, V: MessageVisitor> Visitor<'de> for Results<'_, V> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of TRAPI Results")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(result) = seq.next_element::<crate::Result>()? {
+            self.visitor.result(result);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum WriteError {
+    Json(serde_json::Error),
+    OutOfOrder { section: &'static str, after: &'static str },
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Json(e) => write!(f, "could not write response: {}", e),
+            WriteError::OutOfOrder { section, after } => write!(f, "cannot write {} after {}", section, after),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+impl From<serde_json::Error> for WriteError {
+    fn from(e: serde_json::Error) -> Self {
+        WriteError::Json(e)
+    }
+}
+
+impl From<std::io::Error> for WriteError {
+    fn from(e: std::io::Error) -> Self {
+        WriteError::Json(serde_json::Error::io(e))
+    }
+}
+
+// the parts of a message, in the order Message serializes them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Start,
+    Results,
+    QueryGraph,
+    Edges,
+    Nodes,
+    KnowledgeGraphExtensions,
+    AuxiliaryGraphs,
+    #[cfg(feature = "extensions")]
+    MessageExtensions,
+    End,
+}
+
+impl Section {
+    fn name(&self) -> &'static str {
+        match self {
+            Section::Start => "the start of the message",
+            Section::Results => "results",
+            Section::QueryGraph => "the query graph",
+            Section::Edges => "knowledge graph edges",
+            Section::Nodes => "knowledge graph nodes",
+            Section::KnowledgeGraphExtensions => "knowledge graph extensions",
+            Section::AuxiliaryGraphs => "auxiliary graphs",
+            #[cfg(feature = "extensions")]
+            Section::MessageExtensions => "message extensions",
+            Section::End => "the end of the message",
+        }
+    }
+
+    fn in_knowledge_graph(&self) -> bool {
+        (Section::Edges..=Section::KnowledgeGraphExtensions).contains(self)
+    }
+}
+
+/// Writes a `Response` one graph element or result at a time, producing exactly the bytes `serde_json::to_writer`
+/// would for the equivalent `Response`. Elements must come in the order `Message` serializes them: results, the query
+/// graph, knowledge graph edges, knowledge graph nodes, then auxiliary graphs. A section nothing was written to is left
+/// out as if it were `None`, except that a knowledge graph always gets both its `edges` and its `nodes`;
+/// `begin_results` and friends write a section that is present but empty.
+pub struct ResponseWriter<W: Write> {
+    writer: W,
+    section: Section,
+    empty: bool,
+    message_empty: bool,
+}
+
+impl<W: Write> ResponseWriter<W> {
+    /// Starts the response; `workflow` goes first because `Response` serializes it before the message.
+    pub fn new(mut writer: W, workflow: Option<&Vec<Workflow>>) -> Result<ResponseWriter<W>, WriteError> {
+        writer.write_all(b"{")?;
+        if let Some(workflow) = workflow {
+            writer.write_all(b"\"workflow\":")?;
+            serde_json::to_writer(&mut writer, workflow)?;
+            writer.write_all(b",")?;
+        }
+        writer.write_all(b"\"message\":{")?;
+        Ok(ResponseWriter {
+            writer,
+            section: Section::Start,
+            empty: true,
+            message_empty: true,
+        })
+    }
+
+    // writes the name of the message's next member
+    fn member(&mut self, name: &str) -> Result<(), WriteError> {
+        if !self.message_empty {
+            self.writer.write_all(b",")?;
+        }
+        self.message_empty = false;
+        serde_json::to_writer(&mut self.writer, name)?;
+        self.writer.write_all(b":")?;
+        Ok(())
+    }
+
+    // moves on to `section`, closing the current one, unless it is already open
+    fn open(&mut self, section: Section) -> Result<(), WriteError> {
+        if section == self.section && section != Section::QueryGraph {
+            return Ok(());
+        }
+        if section <= self.section {
+            return Err(WriteError::OutOfOrder {
+                section: section.name(),
+                after: self.section.name(),
+            });
+        }
+
+        match self.section {
+            Section::Results => self.writer.write_all(b"]")?,
+            Section::Edges | Section::Nodes | Section::AuxiliaryGraphs => self.writer.write_all(b"}")?,
+            _ => {}
+        }
+        // a knowledge graph always has its edges followed by its nodes
+        if self.section == Section::Edges && section > Section::Nodes {
+            self.writer.write_all(b",\"nodes\":{}")?;
+        }
+        if self.section.in_knowledge_graph() && !section.in_knowledge_graph() {
+            self.writer.write_all(b"}")?;
+        }
+        if !self.section.in_knowledge_graph() && section.in_knowledge_graph() {
+            self.member("knowledge_graph")?;
+            self.writer.write_all(b"{\"edges\":{")?;
+            if section != Section::Edges {
+                self.writer.write_all(b"}")?;
+            }
+            if section == Section::KnowledgeGraphExtensions {
+                self.writer.write_all(b",\"nodes\":{}")?;
+            }
+        }
+
+        match section {
+            Section::Results => {
+                self.member("results")?;
+                self.writer.write_all(b"[")?;
+            }
+            Section::QueryGraph => self.member("query_graph")?,
+            Section::Nodes => self.writer.write_all(b",\"nodes\":{")?,
+            Section::AuxiliaryGraphs => {
+                self.member("auxiliary_graphs")?;
+                self.writer.write_all(b"{")?;
+            }
+            _ => {}
+        }
+        self.section = section;
+        self.empty = true;
+        Ok(())
+    }
+
+    // opens `section` if needed and writes the separator before its next element
+    fn element(&mut self, section: Section) -> Result<(), WriteError> {
+        self.open(section)?;
+        if !self.empty {
+            self.writer.write_all(b",")?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    fn entry<T: Serialize>(&mut self, section: Section, id: &str, value: &T) -> Result<(), WriteError> {
+        self.element(section)?;
+        serde_json::to_writer(&mut self.writer, id)?;
+        self.writer.write_all(b":")?;
+        serde_json::to_writer(&mut self.writer, value)?;
+        Ok(())
+    }
+
+    pub fn result(&mut self, result: &crate::Result) -> Result<(), WriteError> {
+        self.element(Section::Results)?;
+        serde_json::to_writer(&mut self.writer, result)?;
+        Ok(())
+    }
+
+    pub fn query_graph(&mut self, query_graph: &QueryGraph) -> Result<(), WriteError> {
+        self.open(Section::QueryGraph)?;
+        serde_json::to_writer(&mut self.writer, query_graph)?;
+        Ok(())
+    }
+
+    pub fn edge(&mut self, id: &str, edge: &Edge) -> Result<(), WriteError> {
+        self.entry(Section::Edges, id, edge)
+    }
+
+    pub fn node(&mut self, id: &str, node: &Node) -> Result<(), WriteError> {
+        self.entry(Section::Nodes, id, node)
+    }
+
+    /// Writes the members of `KnowledgeGraph::extensions`, after the knowledge graph's edges and nodes.
+    #[cfg(feature = "extensions")]
+    pub fn knowledge_graph_extensions(&mut self, extensions: &crate::Extensions) -> Result<(), WriteError> {
+        for (name, value) in extensions {
+            self.open(Section::KnowledgeGraphExtensions)?;
+            self.writer.write_all(b",")?;
+            serde_json::to_writer(&mut self.writer, name)?;
+            self.writer.write_all(b":")?;
+            serde_json::to_writer(&mut self.writer, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn auxiliary_graph(&mut self, id: &str, auxiliary_graph: &AuxiliaryGraph) -> Result<(), WriteError> {
+        self.entry(Section::AuxiliaryGraphs, id, auxiliary_graph)
+    }
+
+    /// Writes the members of `Message::extensions`, after everything else in the message.
+    #[cfg(feature = "extensions")]
+    pub fn message_extensions(&mut self, extensions: &crate::Extensions) -> Result<(), WriteError> {
+        for (name, value) in extensions {
+            self.open(Section::MessageExtensions)?;
+            self.member(name)?;
+            serde_json::to_writer(&mut self.writer, value)?;
+        }
+        Ok(())
+    }
+
+    /// Writes `results` even if no result follows.
+    pub fn begin_results(&mut self) -> Result<(), WriteError> {
+        self.open(Section::Results)
+    }
+
+    /// Writes the knowledge graph even if it turns out to have no edges or nodes.
+    pub fn begin_knowledge_graph(&mut self) -> Result<(), WriteError> {
+        self.open(Section::Edges)
+    }
+
+    /// Writes `auxiliary_graphs` even if no auxiliary graph follows.
+    pub fn begin_auxiliary_graphs(&mut self) -> Result<(), WriteError> {
+        self.open(Section::AuxiliaryGraphs)
+    }
+
+    /// Closes the message, writes the rest of `header` and hands back the underlying writer. `header.workflow` is
+    /// ignored since it was written by `new`.
+    pub fn finish(mut self, header: &ResponseHeader) -> Result<W, WriteError> {
+        self.open(Section::End)?;
+        self.writer.write_all(b"}")?;
+        self.field("status", &header.status)?;
+        self.field("description", &header.description)?;
+        self.field("logs", &header.logs)?;
+        self.field("schema_version", &header.schema_version)?;
+        self.field("biolink_version", &header.biolink_version)?;
+        #[cfg(feature = "extensions")]
+        for (name, value) in &header.extensions {
+            self.field(name, &Some(value))?;
+        }
+        self.writer.write_all(b"}")?;
+        Ok(self.writer)
+    }
+
+    fn field<T: Serialize>(&mut self, name: &str, value: &Option<T>) -> Result<(), WriteError> {
+        if let Some(value) = value {
+            self.writer.write_all(b",")?;
+            serde_json::to_writer(&mut self.writer, name)?;
+            self.writer.write_all(b":")?;
+            serde_json::to_writer(&mut self.writer, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::stream::{read_message, read_response, skim_response, MessageVisitor, ResponseHeader, ResponseWriter, WriteError};
+    use crate::{AuxiliaryGraph, Edge, LogLevel, Message, Node, QueryGraph, Response};
+
+    const RESPONSE: &str = r#"{
+        "status": "Success",
+        "schema_version": "1.5.0",
+        "biolink_version": "4.2.0",
+        "message": {
+            "query_graph": {
+                "nodes": { "n0": { "categories": ["biolink:ChemicalEntity"] }, "n1": { "ids": ["MONDO:0005148"] } },
+                "edges": { "e0": { "subject": "n0", "object": "n1", "predicates": ["biolink:treats"] } }
+            },
+            "knowledge_graph": {
+                "nodes": {
+                    "CHEBI:6801": { "name": "metformin", "categories": ["biolink:SmallMolecule"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {
+                    "e01": { "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [] }
+                }
+            },
+            "auxiliary_graphs": null,
+            "results": [
+                {
+                    "node_bindings": { "n0": [{ "id": "CHEBI:6801", "attributes": [] }], "n1": [{ "id": "MONDO:0005148", "attributes": [] }] },
+                    "analyses": [{ "resource_id": "infores:kp0", "edge_bindings": { "e0": [{ "id": "e01", "attributes": [] }] } }]
+                }
+            ]
+        },
+        "logs": [{ "timestamp": "2024-05-01T00:00:00Z", "level": "INFO", "message": "done" }]
+    }"#;
+
+    #[derive(Default)]
+    struct Collect {
+        query_graphs: Vec<QueryGraph>,
+        nodes: Vec<(String, Node)>,
+        edges: Vec<(String, Edge)>,
+        results: Vec<crate::Result>,
+    }
+
+    impl MessageVisitor for Collect {
+        fn query_graph(&mut self, query_graph: QueryGraph) {
+            self.query_graphs.push(query_graph);
+        }
+
+        fn node(&mut self, id: String, node: Node) {
+            self.nodes.push((id, node));
+        }
+
+        fn edge(&mut self, id: String, edge: Edge) {
+            self.edges.push((id, edge));
+        }
+
+        fn result(&mut self, result: crate::Result) {
+            self.results.push(result);
+        }
+    }
+
+    #[test]
+    fn visit_response() {
+        let mut collect = Collect::default();
+        let header = read_response(RESPONSE.as_bytes(), &mut collect).unwrap();
+        assert_eq!(header.status.as_deref(), Some("Success"));
+        assert_eq!(header.logs.unwrap()[0].level, Some(LogLevel::INFO));
+
+        let response: Response = serde_json::from_str(RESPONSE).unwrap();
+        let kg = response.message.knowledge_graph.unwrap();
+        assert_eq!(collect.query_graphs, vec![response.message.query_graph.unwrap()]);
+        assert_eq!(collect.nodes.len(), 2);
+        collect.nodes.iter().for_each(|(id, node)| assert_eq!(&kg.nodes[id], node));
+        assert_eq!(collect.edges, vec![("e01".to_string(), kg.edges["e01"].clone())]);
+        assert_eq!(collect.results, response.message.results.unwrap());
+    }
+
+    #[test]
+    fn skim() {
+        let header = skim_response(RESPONSE.as_bytes()).unwrap();
+        assert_eq!(header.schema_version.as_deref(), Some("1.5.0"));
+        assert_eq!(header.biolink_version.as_deref(), Some("4.2.0"));
+        assert_eq!(header.logs.map(|logs| logs.len()), Some(1));
+
+        // skimming still checks the JSON is well formed
+        assert!(skim_response(&RESPONSE.as_bytes()[..RESPONSE.len() - 10]).is_err());
+        assert!(skim_response(r#"{ "status": "Success" }"#.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn visit_message() {
+        let mut collect = Collect::default();
+        let message: Message = serde_json::from_str::<Response>(RESPONSE).unwrap().message;
+        read_message(serde_json::to_string(&message).unwrap().as_bytes(), &mut collect).unwrap();
+        assert_eq!(collect.results.len(), 1);
+
+        let error = read_message(r#"{ "results": [{ "node_bindings": {} }] }"#.as_bytes(), &mut Collect::default()).unwrap_err();
+        assert!(error.to_string().contains("analyses"));
+    }
+
+    fn write(response: &Response) -> Vec<u8> {
+        let message = &response.message;
+        let mut writer = ResponseWriter::new(Vec::new(), response.workflow.as_ref()).unwrap();
+        if let Some(results) = &message.results {
+            writer.begin_results().unwrap();
+            results.iter().for_each(|result| writer.result(result).unwrap());
+        }
+        if let Some(query_graph) = &message.query_graph {
+            writer.query_graph(query_graph).unwrap();
+        }
+        if let Some(kg) = &message.knowledge_graph {
+            writer.begin_knowledge_graph().unwrap();
+            kg.edges.iter().for_each(|(id, edge)| writer.edge(id, edge).unwrap());
+            kg.nodes.iter().for_each(|(id, node)| writer.node(id, node).unwrap());
+            #[cfg(feature = "extensions")]
+            writer.knowledge_graph_extensions(&kg.extensions).unwrap();
+        }
+        if let Some(auxiliary_graphs) = &message.auxiliary_graphs {
+            writer.begin_auxiliary_graphs().unwrap();
+            auxiliary_graphs.iter().for_each(|(id, graph)| writer.auxiliary_graph(id, graph).unwrap());
+        }
+        #[cfg(feature = "extensions")]
+        writer.message_extensions(&message.extensions).unwrap();
+        let header = ResponseHeader {
+            workflow: None,
+            status: response.status.clone(),
+            description: response.description.clone(),
+            logs: response.logs.clone(),
+            schema_version: response.schema_version.clone(),
+            biolink_version: response.biolink_version.clone(),
+            #[cfg(feature = "extensions")]
+            extensions: response.extensions.clone(),
+        };
+        writer.finish(&header).unwrap()
+    }
+
+    #[test]
+    fn write_response() {
+        let mut response: Response = serde_json::from_str(RESPONSE).unwrap();
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+
+        let graph: AuxiliaryGraph = serde_json::from_str(r#"{ "edges": ["e01"], "attributes": [] }"#).unwrap();
+        response.message.auxiliary_graphs = Some([("ag1".to_string(), graph.clone()), ("ag2".to_string(), graph)].into());
+        response.workflow = serde_json::from_str(r#"[{ "id": "lookup" }]"#).unwrap();
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+
+        // empty and missing sections
+        response.message.knowledge_graph.as_mut().unwrap().edges.clear();
+        response.message.query_graph = None;
+        response.message.results = Some(vec![]);
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+        response.message.knowledge_graph.as_mut().unwrap().nodes.clear();
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+        response.message = Message::default();
+        response.logs = None;
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn write_extensions() {
+        use serde_json::Value;
+
+        let mut response: Response = serde_json::from_str(RESPONSE).unwrap();
+        response.extensions.insert("x-trace".to_string(), Value::from("abc"));
+        response.message.extensions.insert("x-batch".to_string(), Value::from(1));
+        let kg = response.message.knowledge_graph.as_mut().unwrap();
+        kg.extensions.insert("x-build".to_string(), Value::from(7));
+        kg.extensions.insert("x-source".to_string(), Value::from("kp0"));
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+        assert_eq!(skim_response(write(&response).as_slice()).unwrap().extensions["x-trace"], "abc");
+
+        // extensions alone still give a knowledge graph with edges and nodes
+        response.message = Message::default();
+        let mut kg = crate::KnowledgeGraph::default();
+        kg.extensions.insert("x-build".to_string(), Value::from(7));
+        response.message.knowledge_graph = Some(kg);
+        response.message.extensions.insert("x-batch".to_string(), Value::from(1));
+        assert_eq!(write(&response), serde_json::to_vec(&response).unwrap());
+    }
+
+    #[test]
+    fn write_out_of_order() {
+        let response: Response = serde_json::from_str(RESPONSE).unwrap();
+        let kg = response.message.knowledge_graph.unwrap();
+        let mut writer = ResponseWriter::new(Vec::new(), None).unwrap();
+        writer.node("CHEBI:6801", &kg.nodes["CHEBI:6801"]).unwrap();
+        assert!(matches!(writer.edge("e01", &kg.edges["e01"]), Err(WriteError::OutOfOrder { .. })));
+        assert!(writer.query_graph(&response.message.query_graph.unwrap()).is_err());
+        assert!(writer.result(&response.message.results.unwrap()[0]).is_err());
+
+        // nodes without edges still give a complete knowledge graph
+        let mut writer = ResponseWriter::new(Vec::new(), None).unwrap();
+        writer.node("CHEBI:6801", &kg.nodes["CHEBI:6801"]).unwrap();
+        let written = writer.finish(&ResponseHeader::default()).unwrap();
+        let response: Response = serde_json::from_slice(&written).unwrap();
+        assert!(response.message.knowledge_graph.unwrap().edges.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::validation::escape_pointer_token;
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::Serialize;
+use serde_ignored::Path;
+use std::fmt;
+use std::io::Read;
+#[cfg(feature = "extensions")]
+use {serde::Serializer, serde_json::Value, std::cell::Cell};
+
+/// A member the model has no field for, located by a JSON pointer (RFC 6901) relative to the parsed document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownField {
+    pub path: String,
+}
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: unknown field", self.path)
+    }
+}
+
+#[derive(Debug)]
+pub enum StrictError {
+    Json(serde_json::Error),
+    UnknownFields(Vec<UnknownField>),
+}
+
+impl fmt::Display for StrictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrictError::Json(e) => write!(f, "could not parse: {}", e),
+            StrictError::UnknownFields(fields) => {
+                let paths: Vec<&str> = fields.iter().map(|field| field.path.as_str()).collect();
+                write!(f, "unknown fields: {}", paths.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for StrictError {}
+
+impl From<serde_json::Error> for StrictError {
+    fn from(e: serde_json::Error) -> Self {
+        StrictError::Json(e)
+    }
+}
+
+fn pointer(path: &Path) -> String {
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}/{}", pointer(parent), index),
+        Path::Map { parent, key } => format!("{}/{}", pointer(parent), escape_pointer_token(key)),
+        Path::Some { parent } | Path::NewtypeStruct { parent } | Path::NewtypeVariant { parent } => pointer(parent),
+    }
+}
+
+#[cfg(feature = "extensions")]
+thread_local! {
+    static MARK_EXTENSIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+// put in front of the names of extension members while `extension_fields` serializes a value, to tell them from fields
+#[cfg(feature = "extensions")]
+const MARKER: char = '\u{0}';
+
+/// Serializes an `extensions` map as usual, except while `extension_fields` is looking for its members.
+#[cfg(feature = "extensions")]
+pub(crate) fn serialize_extensions<S: Serializer>(extensions: &crate::Extensions, serializer: S) -> Result<S::Ok, S::Error> {
+    if MARK_EXTENSIONS.with(Cell::get) {
+        serializer.collect_map(extensions.iter().map(|(name, value)| (format!("{}{}", MARKER, name), value)))
+    } else {
+        serializer.collect_map(extensions)
+    }
+}
+
+// every member of `value` that was kept in an `extensions` map
+#[cfg(feature = "extensions")]
+fn extension_fields<T: Serialize>(value: &T) -> Vec<UnknownField> {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            MARK_EXTENSIONS.with(|mark| mark.set(self.0));
+        }
+    }
+
+    fn walk(value: &Value, path: &str, found: &mut Vec<UnknownField>) {
+        match value {
+            Value::Object(members) => members.iter().for_each(|(name, member)| match name.strip_prefix(MARKER) {
+                Some(name) => found.push(UnknownField {
+                    path: format!("{}/{}", path, escape_pointer_token(name)),
+                }),
+                None => walk(member, &format!("{}/{}", path, escape_pointer_token(name)), found),
+            }),
+            Value::Array(items) => items.iter().enumerate().for_each(|(idx, item)| walk(item, &format!("{}/{}", path, idx), found)),
+            _ => {}
+        }
+    }
+
+    let marked = {
+        let _restore = Restore(MARK_EXTENSIONS.with(|mark| mark.replace(true)));
+        serde_json::to_value(value).unwrap_or_default()
+    };
+    let mut found = vec![];
+    walk(&marked, "", &mut found);
+    found
+}
+
+/// Deserializes a `T` as usual, also returning every member `T` has no field for, sorted by path. That includes the
+/// members the `extensions` feature keeps in `extensions` maps.
+pub fn deserialize_reporting<'de, D: Deserializer<'de>, T: DeserializeOwned + Serialize>(deserializer: D) -> Result<(T, Vec<UnknownField>), D::Error> {
+    let mut unknown = vec![];
+    let value = serde_ignored::deserialize(deserializer, |path| unknown.push(UnknownField { path: pointer(&path) }))?;
+    #[cfg(feature = "extensions")]
+    unknown.extend(extension_fields(&value));
+    unknown.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((value, unknown))
+}
+
+fn strict<'de, D: Deserializer<'de, Error = serde_json::Error>, T: DeserializeOwned + Serialize>(deserializer: D) -> Result<T, StrictError> {
+    match deserialize_reporting(deserializer)? {
+        (value, unknown) if unknown.is_empty() => Ok(value),
+        (_, unknown) => Err(StrictError::UnknownFields(unknown)),
+    }
+}
+
+/// Parses `s` like `serde_json::from_str`, but fails listing every unknown member rather than dropping them.
+pub fn from_str<T: DeserializeOwned + Serialize>(s: &str) -> Result<T, StrictError> {
+    let mut deserializer = serde_json::Deserializer::from_str(s);
+    let value = strict(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+pub fn from_slice<T: DeserializeOwned + Serialize>(v: &[u8]) -> Result<T, StrictError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(v);
+    let value = strict(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+pub fn from_reader<R: Read, T: DeserializeOwned + Serialize>(reader: R) -> Result<T, StrictError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let value = strict(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::strict::{from_str, StrictError};
+
+    #[test]
+    fn misspelled_predicates() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": { "n0": { "ids": ["MONDO:0005148"] }, "n1": { "categories": ["biolink:ChemicalEntity"] } },
+                    "edges": { "e0": { "subject": "n1", "object": "n0", "predicate": ["biolink:treats"] } }
+                }
+            }
+        }"#;
+        assert!(serde_json::from_str::<crate::Query>(data).is_ok());
+        match from_str::<crate::Query>(data) {
+            Err(StrictError::UnknownFields(fields)) => assert_eq!(
+                fields,
+                vec![crate::strict::UnknownField {
+                    path: "/message/query_graph/edges/e0/predicate".to_string()
+                }]
+            ),
+            other => panic!("expected unknown fields, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn all_unknown_fields() {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": { "CHEBI:6801": { "categories": ["biolink:SmallMolecule"], "attributes": [], "synonym": "metformin" } },
+                "edges": {
+                    "a/b": {
+                        "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [],
+                        "attributes": [{ "attribute_type_id": "biolink:p_value", "value": 0.01, "units": "none" }]
+                    }
+                }
+            },
+            "results": [],
+            "score": 1
+        }"#;
+        let Err(StrictError::UnknownFields(fields)) = from_str::<crate::Message>(data) else {
+            panic!("expected unknown fields");
+        };
+        let paths: Vec<&str> = fields.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["/knowledge_graph/edges/a~1b/attributes/0/units", "/knowledge_graph/nodes/CHEBI:6801/synonym", "/score"]
+        );
+
+        assert!(matches!(from_str::<crate::Message>(r#"{ "results": [{}] }"#), Err(StrictError::Json(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn extensions_are_reported() {
+        let data = r#"{ "subject": "n1", "object": "n0", "predicate": ["biolink:treats"] }"#;
+        let qedge: crate::QEdge = serde_json::from_str(data).unwrap();
+        assert!(qedge.extensions.contains_key("predicate"));
+        let Err(StrictError::UnknownFields(fields)) = from_str::<crate::QEdge>(data) else {
+            panic!("expected unknown fields");
+        };
+        assert_eq!(fields, vec![crate::strict::UnknownField { path: "/predicate".to_string() }]);
+
+        // extension members are written back out under their own names
+        assert_eq!(serde_json::to_value(&qedge).unwrap()["predicate"][0], "biolink:treats");
+    }
+}
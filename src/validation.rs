@@ -0,0 +1,536 @@
+use crate::{curie, AsyncQuery, Attribute, AttributeConstraint, KnowledgeGraph, Message, Qualifier, Query, QueryGraph, Response};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::sync::OnceLock;
+
+pub const CURIE_PATTERN: &str = r"^[A-Za-z_][A-Za-z0-9_.\-]*:\S+$";
+pub const BIOLINK_ENTITY_PATTERN: &str = r"^biolink:[A-Z][a-zA-Z]*$";
+pub const BIOLINK_PREDICATE_PATTERN: &str = r"^biolink:[a-z][a-z_]*$";
+pub const CALLBACK_PATTERN: &str = r"^https?://";
+
+pub(crate) fn curie_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(CURIE_PATTERN).expect("invalid CURIE pattern"))
+}
+
+pub(crate) fn biolink_entity_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(BIOLINK_ENTITY_PATTERN).expect("invalid BiolinkEntity pattern"))
+}
+
+pub(crate) fn biolink_predicate_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(BIOLINK_PREDICATE_PATTERN).expect("invalid BiolinkPredicate pattern"))
+}
+
+fn callback_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(CALLBACK_PATTERN).expect("invalid callback pattern"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    MalformedCurie,
+    InvalidCategory,
+    InvalidPredicate,
+    InvalidQualifierType,
+    InvalidCallback,
+    EmptyList,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ViolationKind::MalformedCurie => "malformed CURIE",
+            ViolationKind::InvalidCategory => "not a biolink category",
+            ViolationKind::InvalidPredicate => "not a biolink predicate",
+            ViolationKind::InvalidQualifierType => "not a biolink qualifier type",
+            ViolationKind::InvalidCallback => "not an http(s) callback",
+            ViolationKind::EmptyList => "empty list",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// A single structural problem, located by a JSON pointer (RFC 6901) relative to the validated object.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub path: String,
+
+    pub kind: ViolationKind,
+
+    pub value: Option<String>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: {} ({:?})", self.path, self.kind, value),
+            None => write!(f, "{}: {}", self.path, self.kind),
+        }
+    }
+}
+
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parses `s` like `serde_json::from_str`, but keeps malformed CURIEs, categories and predicates as they are instead of
+/// failing on the first one, so that `validate` can report every one of them with its location.
+pub fn from_str_lenient<T: DeserializeOwned>(s: &str) -> serde_json::Result<T> {
+    curie::lenient(|| serde_json::from_str(s))
+}
+
+pub fn from_slice_lenient<T: DeserializeOwned>(v: &[u8]) -> serde_json::Result<T> {
+    curie::lenient(|| serde_json::from_slice(v))
+}
+
+pub fn from_value_lenient<T: DeserializeOwned>(value: Value) -> serde_json::Result<T> {
+    curie::lenient(|| serde_json::from_value(value))
+}
+
+// CURIE syntax is only enforced at deserialization time by the strict parse, so it is checked again here for values
+// that were parsed leniently or built in code
+struct Validator {
+    path: Vec<String>,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    fn new() -> Validator {
+        Validator { path: vec![], violations: vec![] }
+    }
+
+    fn scoped<F: FnOnce(&mut Validator)>(&mut self, token: &str, f: F) {
+        self.path.push(escape_pointer_token(token));
+        f(self);
+        self.path.pop();
+    }
+
+    fn pointer(&self, token: Option<&str>) -> String {
+        let mut pointer: String = self.path.iter().map(|p| format!("/{}", p)).collect();
+        if let Some(token) = token {
+            pointer.push('/');
+            pointer.push_str(&escape_pointer_token(token));
+        }
+        pointer
+    }
+
+    fn report(&mut self, token: Option<&str>, kind: ViolationKind, value: Option<&str>) {
+        let path = self.pointer(token);
+        self.violations.push(Violation {
+            path,
+            kind,
+            value: value.map(String::from),
+        });
+    }
+
+    fn check(&mut self, token: &str, value: &str, regex: &Regex, kind: ViolationKind) {
+        if !regex.is_match(value) {
+            self.report(Some(token), kind, Some(value));
+        }
+    }
+
+    fn check_list<S: AsRef<str>>(&mut self, token: &str, values: &Option<Vec<S>>, regex: &Regex, kind: ViolationKind) {
+        if let Some(values) = values {
+            self.scoped(token, |v| {
+                values
+                    .iter()
+                    .enumerate()
+                    .for_each(|(idx, value)| v.check(&idx.to_string(), value.as_ref(), regex, kind.clone()));
+            });
+        }
+    }
+
+    // for the lists the schema gives a minItems of 1
+    fn check_non_empty_list<S: AsRef<str>>(&mut self, token: &str, values: &Option<Vec<S>>, regex: &Regex, kind: ViolationKind) {
+        if values.as_ref().is_some_and(|values| values.is_empty()) {
+            self.report(Some(token), ViolationKind::EmptyList, None);
+        }
+        self.check_list(token, values, regex, kind);
+    }
+
+    fn attributes(&mut self, token: &str, attributes: &[Attribute]) {
+        self.scoped(token, |v| {
+            attributes.iter().enumerate().for_each(|(idx, attribute)| {
+                v.scoped(&idx.to_string(), |v| {
+                    v.check("attribute_type_id", attribute.attribute_type_id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                    if let Some(value_type_id) = &attribute.value_type_id {
+                        v.check("value_type_id", value_type_id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                    }
+                    if let Some(attributes) = &attribute.attributes {
+                        v.attributes("attributes", attributes);
+                    }
+                });
+            });
+        });
+    }
+
+    fn constraints(&mut self, token: &str, constraints: &[AttributeConstraint]) {
+        self.scoped(token, |v| {
+            constraints.iter().enumerate().for_each(|(idx, constraint)| {
+                v.scoped(&idx.to_string(), |v| v.check("id", constraint.id.as_str(), curie_regex(), ViolationKind::MalformedCurie));
+            });
+        });
+    }
+
+    fn qualifiers(&mut self, token: &str, qualifiers: &[Qualifier]) {
+        self.scoped(token, |v| {
+            qualifiers.iter().enumerate().for_each(|(idx, qualifier)| {
+                v.scoped(&idx.to_string(), |v| {
+                    v.check(
+                        "qualifier_type_id",
+                        qualifier.qualifier_type_id.as_str(),
+                        biolink_predicate_regex(),
+                        ViolationKind::InvalidQualifierType,
+                    )
+                });
+            });
+        });
+    }
+
+    fn query_graph(&mut self, query_graph: &QueryGraph) {
+        self.scoped("nodes", |v| {
+            query_graph.nodes.iter().for_each(|(key, node)| {
+                v.scoped(key, |v| {
+                    v.check_non_empty_list("ids", &node.ids, curie_regex(), ViolationKind::MalformedCurie);
+                    v.check_non_empty_list("categories", &node.categories, biolink_entity_regex(), ViolationKind::InvalidCategory);
+                    v.check_list("member_ids", &node.member_ids, curie_regex(), ViolationKind::MalformedCurie);
+                    if let Some(constraints) = &node.constraints {
+                        v.constraints("constraints", constraints);
+                    }
+                });
+            });
+        });
+        self.scoped("edges", |v| {
+            query_graph.edges.iter().for_each(|(key, edge)| {
+                v.scoped(key, |v| {
+                    v.check_non_empty_list("predicates", &edge.predicates, biolink_predicate_regex(), ViolationKind::InvalidPredicate);
+                    if let Some(constraints) = &edge.attribute_constraints {
+                        v.constraints("attribute_constraints", constraints);
+                    }
+                    if let Some(constraints) = &edge.qualifier_constraints {
+                        v.scoped("qualifier_constraints", |v| {
+                            constraints.iter().enumerate().for_each(|(idx, constraint)| {
+                                v.scoped(&idx.to_string(), |v| v.qualifiers("qualifier_set", &constraint.qualifier_set));
+                            });
+                        });
+                    }
+                });
+            });
+        });
+    }
+
+    fn knowledge_graph(&mut self, knowledge_graph: &KnowledgeGraph) {
+        // the knowledge graph maps are unordered, so keys are sorted to report violations in the same order every time
+        let mut node_ids: Vec<&String> = knowledge_graph.nodes.keys().collect();
+        node_ids.sort();
+        self.scoped("nodes", |v| {
+            node_ids.into_iter().for_each(|key| {
+                let node = &knowledge_graph.nodes[key];
+                v.check(key, key, curie_regex(), ViolationKind::MalformedCurie);
+                v.scoped(key, |v| {
+                    v.scoped("categories", |v| {
+                        node.categories.iter().enumerate().for_each(|(idx, category)| {
+                            v.check(&idx.to_string(), category.as_str(), biolink_entity_regex(), ViolationKind::InvalidCategory);
+                        });
+                    });
+                    v.attributes("attributes", &node.attributes);
+                });
+            });
+        });
+        let mut edge_ids: Vec<&String> = knowledge_graph.edges.keys().collect();
+        edge_ids.sort();
+        self.scoped("edges", |v| {
+            edge_ids.into_iter().for_each(|key| {
+                let edge = &knowledge_graph.edges[key];
+                v.scoped(key, |v| {
+                    v.check("subject", edge.subject.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                    v.check("predicate", edge.predicate.as_str(), biolink_predicate_regex(), ViolationKind::InvalidPredicate);
+                    v.check("object", edge.object.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                    v.scoped("sources", |v| {
+                        edge.sources.iter().enumerate().for_each(|(idx, source)| {
+                            v.scoped(&idx.to_string(), |v| {
+                                v.check("resource_id", source.resource_id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                                if let Some(upstream_resource_ids) = &source.upstream_resource_ids {
+                                    v.scoped("upstream_resource_ids", |v| {
+                                        upstream_resource_ids
+                                            .iter()
+                                            .enumerate()
+                                            .for_each(|(idx, upstream_id)| v.check(&idx.to_string(), upstream_id.as_str(), curie_regex(), ViolationKind::MalformedCurie));
+                                    });
+                                }
+                            });
+                        });
+                    });
+                    if let Some(attributes) = &edge.attributes {
+                        v.attributes("attributes", attributes);
+                    }
+                    if let Some(qualifiers) = &edge.qualifiers {
+                        v.qualifiers("qualifiers", qualifiers);
+                    }
+                });
+            });
+        });
+    }
+
+    fn message(&mut self, message: &Message) {
+        if let Some(query_graph) = &message.query_graph {
+            self.scoped("query_graph", |v| v.query_graph(query_graph));
+        }
+        if let Some(knowledge_graph) = &message.knowledge_graph {
+            self.scoped("knowledge_graph", |v| v.knowledge_graph(knowledge_graph));
+        }
+        if let Some(results) = &message.results {
+            self.scoped("results", |v| {
+                results.iter().enumerate().for_each(|(idx, result)| {
+                    v.scoped(&idx.to_string(), |v| {
+                        v.scoped("node_bindings", |v| {
+                            result.node_bindings.iter().for_each(|(key, node_bindings)| {
+                                v.scoped(key, |v| {
+                                    node_bindings.iter().enumerate().for_each(|(idx, node_binding)| {
+                                        v.scoped(&idx.to_string(), |v| {
+                                            v.check("id", node_binding.id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                                            if let Some(query_id) = &node_binding.query_id {
+                                                v.check("query_id", query_id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                                            }
+                                            v.attributes("attributes", &node_binding.attributes);
+                                        });
+                                    });
+                                });
+                            });
+                        });
+                        v.scoped("analyses", |v| {
+                            result.analyses.iter().enumerate().for_each(|(idx, analysis)| {
+                                v.scoped(&idx.to_string(), |v| {
+                                    v.check("resource_id", analysis.resource_id.as_str(), curie_regex(), ViolationKind::MalformedCurie);
+                                    if let Some(attributes) = &analysis.attributes {
+                                        v.attributes("attributes", attributes);
+                                    }
+                                });
+                            });
+                        });
+                    });
+                });
+            });
+        }
+        if let Some(auxiliary_graphs) = &message.auxiliary_graphs {
+            self.scoped("auxiliary_graphs", |v| {
+                auxiliary_graphs.iter().for_each(|(key, auxiliary_graph)| {
+                    v.scoped(key, |v| v.attributes("attributes", &auxiliary_graph.attributes));
+                });
+            });
+        }
+    }
+}
+
+impl Message {
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut validator = Validator::new();
+        validator.message(self);
+        validator.violations
+    }
+}
+
+impl Query {
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut validator = Validator::new();
+        validator.scoped("message", |v| v.message(&self.message));
+        validator.violations
+    }
+}
+
+impl AsyncQuery {
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut validator = Validator::new();
+        validator.scoped("message", |v| v.message(&self.message));
+        validator.check("callback", &self.callback, callback_regex(), ViolationKind::InvalidCallback);
+        validator.violations
+    }
+}
+
+impl Response {
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut validator = Validator::new();
+        validator.scoped("message", |v| v.message(&self.message));
+        validator.violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::validation::{from_str_lenient, Violation, ViolationKind};
+    use crate::{AsyncQuery, Message, Query};
+
+    #[test]
+    fn valid_query() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": {"n1": {"ids": ["MONDO:0009061", "MONDO:0004979"]}, "n0": {"categories": ["biolink:ChemicalEntity"]}},
+                    "edges": {"e0": {"subject": "n0", "object": "n1", "predicates": ["biolink:treats"], "knowledge_type": "inferred"}}
+                }
+            }
+        }"#;
+
+        let query: Query = serde_json::from_str(data).expect("could not parse query");
+        assert!(query.validate().is_empty());
+    }
+
+    #[test]
+    fn invalid_query() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": {"n1": {"ids": []}, "n0": {"categories": ["biolink:ChemicalEntity"]}},
+                    "edges": {
+                        "e0": {
+                            "subject": "n0",
+                            "object": "n1",
+                            "predicates": ["biolink:affects"],
+                            "qualifier_constraints": [{
+                                "qualifier_set": [
+                                    { "qualifier_type_id": "biolink:object_aspect_qualifier", "qualifier_value": "activity" },
+                                    { "qualifier_type_id": "RO:0002213", "qualifier_value": "increased" }
+                                ]
+                            }]
+                        }
+                    }
+                }
+            }
+        }"#;
+
+        let query: Query = serde_json::from_str(data).expect("could not parse query");
+        let violations = query.validate();
+        assert_eq!(
+            violations,
+            vec![
+                Violation {
+                    path: "/message/query_graph/nodes/n1/ids".to_string(),
+                    kind: ViolationKind::EmptyList,
+                    value: None
+                },
+                Violation {
+                    path: "/message/query_graph/edges/e0/qualifier_constraints/0/qualifier_set/1/qualifier_type_id".to_string(),
+                    kind: ViolationKind::InvalidQualifierType,
+                    value: Some("RO:0002213".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_parse() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": {"n1": {"ids": ["donkey", "frizzle chicken"]}, "n0": {"categories": ["poopy pants"]}},
+                    "edges": {"e0": {"subject": "n0", "object": "n1", "predicates": ["biolink:Treats"], "knowledge_type": "inferred"}}
+                },
+                "knowledge_graph": {
+                    "nodes": {},
+                    "edges": {
+                        "e1": { "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO 0005148", "sources": [] }
+                    }
+                }
+            }
+        }"#;
+
+        assert!(serde_json::from_str::<Query>(data).is_err());
+        let query: Query = from_str_lenient(data).expect("could not parse query");
+        let violations = query.validate();
+        assert_eq!(
+            violations,
+            vec![
+                Violation {
+                    path: "/message/query_graph/nodes/n0/categories/0".to_string(),
+                    kind: ViolationKind::InvalidCategory,
+                    value: Some("poopy pants".to_string())
+                },
+                Violation {
+                    path: "/message/query_graph/nodes/n1/ids/0".to_string(),
+                    kind: ViolationKind::MalformedCurie,
+                    value: Some("donkey".to_string())
+                },
+                Violation {
+                    path: "/message/query_graph/nodes/n1/ids/1".to_string(),
+                    kind: ViolationKind::MalformedCurie,
+                    value: Some("frizzle chicken".to_string())
+                },
+                Violation {
+                    path: "/message/query_graph/edges/e0/predicates/0".to_string(),
+                    kind: ViolationKind::InvalidPredicate,
+                    value: Some("biolink:Treats".to_string())
+                },
+                Violation {
+                    path: "/message/knowledge_graph/edges/e1/object".to_string(),
+                    kind: ViolationKind::MalformedCurie,
+                    value: Some("MONDO 0005148".to_string())
+                },
+            ]
+        );
+
+        // the checks are only switched off for the lenient parse
+        assert!(serde_json::from_str::<Query>(data).is_err());
+    }
+
+    #[test]
+    fn empty_member_ids() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": {"n0": {"ids": ["MONDO:0009061"], "member_ids": [], "set_interpretation": "MANY"}},
+                    "edges": {}
+                }
+            }
+        }"#;
+
+        let query: Query = serde_json::from_str(data).expect("could not parse query");
+        assert!(query.validate().is_empty());
+    }
+
+    #[test]
+    fn invalid_async_query_callback() {
+        let data = r#"{
+            "message": {
+                "query_graph": {
+                    "nodes": {"n0": {"ids": ["MONDO:0009061"], "categories": []}},
+                    "edges": {}
+                }
+            },
+            "callback": "SOME_URL"
+        }"#;
+
+        let query: AsyncQuery = serde_json::from_str(data).expect("could not parse query");
+        let violations = query.validate();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].path, "/message/query_graph/nodes/n0/categories");
+        assert_eq!(violations[0].kind, ViolationKind::EmptyList);
+        assert_eq!(violations[1].path, "/callback");
+        assert_eq!(violations[1].kind, ViolationKind::InvalidCallback);
+    }
+
+    #[test]
+    fn knowledge_graph_node_key() {
+        let data = r#"{
+            "knowledge_graph": {
+                "nodes": {
+                    "z/y": { "categories": ["biolink:Disease"], "attributes": [] },
+                    "MONDO:0005148": { "categories": ["biolink:Disease"], "attributes": [] },
+                    "a/b": { "categories": ["biolink:Disease"], "attributes": [] },
+                    "m~n": { "categories": ["biolink:Disease"], "attributes": [] }
+                },
+                "edges": {}
+            }
+        }"#;
+
+        let message: Message = serde_json::from_str(data).expect("could not parse message");
+        let violations = message.validate();
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(paths, vec!["/knowledge_graph/nodes/a~1b", "/knowledge_graph/nodes/m~0n", "/knowledge_graph/nodes/z~1y"]);
+        assert!(violations.iter().all(|v| v.kind == ViolationKind::MalformedCurie));
+        assert_eq!(violations, message.validate());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{Attribute, Edge, Node, CURIE};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The Rust type an attribute value is read as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueType {
+    Float,
+    Integer,
+    Boolean,
+    String,
+    /// a CURIE or a list of them
+    Curie,
+    Uri,
+    DateTime,
+    Date,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ValueType::Float => "float",
+            ValueType::Integer => "integer",
+            ValueType::Boolean => "boolean",
+            ValueType::String => "string",
+            ValueType::Curie => "CURIE",
+            ValueType::Uri => "URI",
+            ValueType::DateTime => "date-time",
+            ValueType::Date => "date",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An attribute value converted according to its type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypedValue {
+    Float(f64),
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+    Curies(Vec<CURIE>),
+    Uri(String),
+    DateTime(DateTime<FixedOffset>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueError {
+    /// the attribute's `value_type_id` declares a type the value cannot be read as
+    TypeMismatch { value_type_id: CURIE, declared: ValueType, requested: ValueType },
+    /// the value does not have the shape of the type it is read as
+    InvalidValue { expected: ValueType, value: Value },
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::TypeMismatch {
+                value_type_id,
+                declared,
+                requested,
+            } => write!(f, "{} values are {}s and cannot be read as {}s", value_type_id, declared, requested),
+            ValueError::InvalidValue { expected, value } => write!(f, "{} is not a valid {}", value, expected),
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Maps `value_type_id` CURIEs to the type their values are read as. Types the registry does not know are not checked.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValueTypeRegistry {
+    types: HashMap<CURIE, ValueType>,
+}
+
+const STANDARD_TYPES: &[(&str, ValueType)] = &[
+    ("xsd:float", ValueType::Float),
+    ("xsd:double", ValueType::Float),
+    ("xsd:decimal", ValueType::Float),
+    ("linkml:Float", ValueType::Float),
+    ("linkml:Double", ValueType::Float),
+    ("xsd:integer", ValueType::Integer),
+    ("xsd:int", ValueType::Integer),
+    ("xsd:long", ValueType::Integer),
+    ("xsd:nonNegativeInteger", ValueType::Integer),
+    ("linkml:Integer", ValueType::Integer),
+    ("xsd:boolean", ValueType::Boolean),
+    ("linkml:Boolean", ValueType::Boolean),
+    ("xsd:string", ValueType::String),
+    ("linkml:String", ValueType::String),
+    ("xsd:anyURI", ValueType::Uri),
+    ("linkml:Uri", ValueType::Uri),
+    ("linkml:Uriorcurie", ValueType::Curie),
+    ("linkml:Curie", ValueType::Curie),
+    ("metatype:Uriorcurie", ValueType::Curie),
+    // PubMed ID
+    ("EDAM:data_1187", ValueType::Curie),
+    ("xsd:dateTime", ValueType::DateTime),
+    ("linkml:Datetime", ValueType::DateTime),
+    ("xsd:date", ValueType::Date),
+    ("linkml:Date", ValueType::Date),
+];
+
+impl ValueTypeRegistry {
+    pub fn new() -> ValueTypeRegistry {
+        ValueTypeRegistry::default()
+    }
+
+    /// The XSD, LinkML and EDAM types commonly found in TRAPI attributes.
+    pub fn standard() -> &'static ValueTypeRegistry {
+        static REGISTRY: OnceLock<ValueTypeRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let mut registry = ValueTypeRegistry::new();
+            STANDARD_TYPES
+                .iter()
+                .for_each(|(id, value_type)| registry.insert(id.parse().expect("standard value type is a valid CURIE"), *value_type));
+            registry
+        })
+    }
+
+    pub fn insert(&mut self, value_type_id: CURIE, value_type: ValueType) {
+        self.types.insert(value_type_id, value_type);
+    }
+
+    pub fn get(&self, value_type_id: &CURIE) -> Option<ValueType> {
+        self.types.get(value_type_id).copied()
+    }
+
+    /// The type `attribute` declares through its `value_type_id`, if the registry knows it.
+    pub fn declared_type(&self, attribute: &Attribute) -> Option<ValueType> {
+        attribute.value_type_id.as_ref().and_then(|id| self.get(id))
+    }
+
+    /// Reads `attribute` as `requested`, failing if it declares a type that cannot be read that way.
+    pub fn read(&self, attribute: &Attribute, requested: ValueType) -> Result<TypedValue, ValueError> {
+        if let (Some(value_type_id), Some(declared)) = (&attribute.value_type_id, self.declared_type(attribute)) {
+            if !compatible(declared, requested) {
+                return Err(ValueError::TypeMismatch {
+                    value_type_id: value_type_id.clone(),
+                    declared,
+                    requested,
+                });
+            }
+        }
+        convert(&attribute.value, requested)
+    }
+
+    /// Reads `attribute` as its declared type, or None if it declares none the registry knows.
+    pub fn typed_value(&self, attribute: &Attribute) -> Option<Result<TypedValue, ValueError>> {
+        self.declared_type(attribute).map(|declared| convert(&attribute.value, declared))
+    }
+}
+
+// integers read fine as floats, dates as date-times and CURIEs as URIs
+fn compatible(declared: ValueType, requested: ValueType) -> bool {
+    declared == requested
+        || matches!(
+            (declared, requested),
+            (ValueType::Integer, ValueType::Float) | (ValueType::Date, ValueType::DateTime) | (ValueType::Curie, ValueType::Uri)
+        )
+}
+
+fn convert(value: &Value, expected: ValueType) -> Result<TypedValue, ValueError> {
+    let invalid = || ValueError::InvalidValue { expected, value: value.clone() };
+    match (expected, value) {
+        (ValueType::Float, Value::Number(n)) => n.as_f64().map(TypedValue::Float).ok_or_else(invalid),
+        (ValueType::Float, Value::String(s)) => s.trim().parse().map(TypedValue::Float).map_err(|_| invalid()),
+        (ValueType::Integer, Value::Number(n)) => n.as_i64().map(TypedValue::Integer).ok_or_else(invalid),
+        (ValueType::Integer, Value::String(s)) => s.trim().parse().map(TypedValue::Integer).map_err(|_| invalid()),
+        (ValueType::Boolean, Value::Bool(b)) => Ok(TypedValue::Boolean(*b)),
+        (ValueType::String, Value::String(s)) => Ok(TypedValue::String(s.clone())),
+        (ValueType::Uri, Value::String(s)) => Ok(TypedValue::Uri(s.clone())),
+        (ValueType::Curie, Value::String(s)) => s.parse().map(|curie| TypedValue::Curies(vec![curie])).map_err(|_| invalid()),
+        (ValueType::Curie, Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().and_then(|s| s.parse().ok()))
+            .collect::<Option<Vec<CURIE>>>()
+            .map(TypedValue::Curies)
+            .ok_or_else(invalid),
+        (ValueType::DateTime | ValueType::Date, Value::String(s)) => DateTime::parse_from_rfc3339(s.trim())
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+                    .map(|midnight| midnight.and_utc().fixed_offset())
+            })
+            .map(TypedValue::DateTime)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+impl Attribute {
+    /// The value as a number; numeric strings are accepted.
+    pub fn as_f64(&self) -> Result<f64, ValueError> {
+        match ValueTypeRegistry::standard().read(self, ValueType::Float)? {
+            TypedValue::Float(f) => Ok(f),
+            _ => unreachable!("floats are read as TypedValue::Float"),
+        }
+    }
+
+    pub fn as_i64(&self) -> Result<i64, ValueError> {
+        match ValueTypeRegistry::standard().read(self, ValueType::Integer)? {
+            TypedValue::Integer(i) => Ok(i),
+            _ => unreachable!("integers are read as TypedValue::Integer"),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, ValueError> {
+        match ValueTypeRegistry::standard().read(self, ValueType::Boolean)? {
+            TypedValue::Boolean(b) => Ok(b),
+            _ => unreachable!("booleans are read as TypedValue::Boolean"),
+        }
+    }
+
+    /// The value as CURIEs, from either a single CURIE or a list of them.
+    pub fn as_curie_list(&self) -> Result<Vec<CURIE>, ValueError> {
+        match ValueTypeRegistry::standard().read(self, ValueType::Curie)? {
+            TypedValue::Curies(curies) => Ok(curies),
+            _ => unreachable!("CURIEs are read as TypedValue::Curies"),
+        }
+    }
+
+    /// The value as an RFC 3339 date-time; a plain `YYYY-MM-DD` date is taken as midnight UTC.
+    pub fn as_datetime(&self) -> Result<DateTime<FixedOffset>, ValueError> {
+        match ValueTypeRegistry::standard().read(self, ValueType::DateTime)? {
+            TypedValue::DateTime(datetime) => Ok(datetime),
+            _ => unreachable!("date-times are read as TypedValue::DateTime"),
+        }
+    }
+
+    /// The attributes nested in this one.
+    pub fn sub_attributes(&self) -> &[Attribute] {
+        self.attributes.as_deref().unwrap_or_default()
+    }
+}
+
+// breadth first, so an attribute is preferred over one nested at a deeper level
+fn find<'a>(attributes: &'a [Attribute], attribute_type_id: &CURIE) -> Option<&'a Attribute> {
+    let mut level: Vec<&'a Attribute> = attributes.iter().collect();
+    while !level.is_empty() {
+        if let Some(found) = level.iter().copied().find(|a| a.attribute_type_id == *attribute_type_id) {
+            return Some(found);
+        }
+        level = level.into_iter().flat_map(|a| a.sub_attributes()).collect();
+    }
+    None
+}
+
+impl Node {
+    /// The first attribute of the given type, looking into nested attributes when there is none at the top level.
+    pub fn attribute(&self, attribute_type_id: &CURIE) -> Option<&Attribute> {
+        find(&self.attributes, attribute_type_id)
+    }
+}
+
+impl Edge {
+    /// The first attribute of the given type, looking into nested attributes when there is none at the top level.
+    pub fn attribute(&self, attribute_type_id: &CURIE) -> Option<&Attribute> {
+        find(self.attributes.as_deref().unwrap_or_default(), attribute_type_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::values::{TypedValue, ValueError, ValueType, ValueTypeRegistry};
+    use crate::{Attribute, Edge};
+
+    fn attribute(data: &str) -> Attribute {
+        serde_json::from_str(data).unwrap()
+    }
+
+    #[test]
+    fn accessors() {
+        let p_value = attribute(r#"{ "attribute_type_id": "biolink:p_value", "value": "1e-5", "value_type_id": "xsd:float" }"#);
+        assert_eq!(p_value.as_f64(), Ok(1e-5));
+        assert!(matches!(p_value.as_curie_list(), Err(ValueError::TypeMismatch { declared: ValueType::Float, .. })));
+
+        let publications = attribute(r#"{ "attribute_type_id": "biolink:publications", "value": ["PMID:1", "PMID:2"], "value_type_id": "linkml:Uriorcurie" }"#);
+        assert_eq!(publications.as_curie_list().unwrap().len(), 2);
+        let pmid = attribute(r#"{ "attribute_type_id": "biolink:publications", "value": "PMID:1", "value_type_id": "EDAM:data_1187" }"#);
+        assert_eq!(pmid.as_curie_list().unwrap(), vec!["PMID:1".parse::<crate::CURIE>().unwrap()]);
+        assert!(pmid.as_f64().is_err());
+
+        let count = attribute(r#"{ "attribute_type_id": "biolink:has_count", "value": 3, "value_type_id": "xsd:integer" }"#);
+        assert_eq!(count.as_i64(), Ok(3));
+        assert_eq!(count.as_f64(), Ok(3.0));
+
+        let date = attribute(r#"{ "attribute_type_id": "biolink:update_date", "value": "2024-05-01", "value_type_id": "xsd:date" }"#);
+        assert_eq!(date.as_datetime().unwrap().to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        let undeclared = attribute(r#"{ "attribute_type_id": "biolink:update_date", "value": "2024-05-01T12:00:00-05:00" }"#);
+        assert_eq!(undeclared.as_datetime().unwrap().to_rfc3339(), "2024-05-01T12:00:00-05:00");
+        assert!(matches!(undeclared.as_bool(), Err(ValueError::InvalidValue { .. })));
+
+        let mut registry = ValueTypeRegistry::new();
+        registry.insert("EDAM:data_1669".parse().unwrap(), ValueType::Float);
+        let custom = attribute(r#"{ "attribute_type_id": "biolink:p_value", "value": 0.5, "value_type_id": "EDAM:data_1669" }"#);
+        assert_eq!(registry.typed_value(&custom), Some(Ok(TypedValue::Float(0.5))));
+        assert_eq!(ValueTypeRegistry::standard().typed_value(&custom), None);
+    }
+
+    #[test]
+    fn nested_lookup() {
+        let data = r#"{
+            "subject": "CHEBI:6801", "predicate": "biolink:treats", "object": "MONDO:0005148", "sources": [],
+            "attributes": [{
+                "attribute_type_id": "biolink:has_supporting_study_result", "value": "study1",
+                "attributes": [
+                    { "attribute_type_id": "biolink:log_odds_ratio", "value": 1.2 },
+                    { "attribute_type_id": "biolink:supporting_study_context", "value": "trial",
+                      "attributes": [{ "attribute_type_id": "biolink:p_value", "value": 0.01, "value_type_id": "xsd:double" }] }
+                ]
+            }]
+        }"#;
+        let edge: Edge = serde_json::from_str(data).unwrap();
+        assert_eq!(edge.attribute(&"biolink:log_odds_ratio".parse().unwrap()).unwrap().as_f64(), Ok(1.2));
+        assert_eq!(edge.attribute(&"biolink:p_value".parse().unwrap()).unwrap().as_f64(), Ok(0.01));
+        assert!(edge.attribute(&"biolink:has_count".parse().unwrap()).is_none());
+    }
+}